@@ -0,0 +1,257 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::expr::ExprKind;
+use crate::grammar::{DefinedAs, Grammar, Name, Rule};
+use crate::report::Report;
+use crate::report_kind::ReportKind;
+
+/// semantic checks on a parsed grammar, the first rule is the start rule
+pub struct Checker<'g, 's> {
+    src: &'s str,
+    grammar: &'g Grammar,
+
+    // every definition of a rule, in source order
+    definitions: HashMap<Name<'g>, Vec<&'g Rule>>,
+    // implicitly defined rules, see `core_rules`
+    core_rules: HashSet<Name<'g>>,
+
+    reports: Vec<Report>,
+}
+
+impl<'g, 's> Checker<'g, 's> {
+    pub fn new(source: &'s str, grammar: &'g Grammar, core_rules: Option<&'g Grammar>) -> Self {
+        let core_rules = core_rules
+            .into_iter()
+            .flat_map(|core| &core.rules)
+            .map(|rule| Name(&rule.name))
+            .collect();
+
+        Checker {
+            src: source,
+            grammar,
+            definitions: grammar.definitions(),
+            core_rules,
+            reports: Vec::new(),
+        }
+    }
+
+    /// every report about the grammar, errors and warnings alike, the
+    /// grammar can still be used whatever they are
+    pub fn check(&mut self) -> Vec<Report> {
+        self.check_definitions();
+        self.check_references();
+
+        std::mem::take(&mut self.reports)
+    }
+
+    // a rule is defined once with = and may be extended with =/ afterwards
+    fn check_definitions(&mut self) {
+        let mut defined: HashSet<Name> = HashSet::new();
+
+        for rule in &self.grammar.rules {
+            let name = Name(&rule.name);
+            let kind = match rule.defined_as {
+                DefinedAs::Basic if !defined.insert(name) => ReportKind::DuplicateRuleError,
+                DefinedAs::Basic if self.core_rules.contains(&name) => {
+                    ReportKind::CoreRuleRedefinitionWarning
+                }
+                DefinedAs::Incremental if !defined.contains(&name) => {
+                    ReportKind::IncrementalBeforeDefinitionError
+                }
+                _ => continue,
+            };
+
+            self.reports
+                .push(Report::from_span(kind, self.src, rule.name_span));
+        }
+    }
+
+    fn check_references(&mut self) {
+        for rule in &self.grammar.rules {
+            rule.expr.walk(&mut |expr| {
+                if let ExprKind::Rule(name) = &expr.kind {
+                    if !self.definitions.contains_key(&Name(name))
+                        && !self.core_rules.contains(&Name(name))
+                    {
+                        let mut report =
+                            Report::from_span(ReportKind::UndefinedRuleError, self.src, expr.span);
+                        if let Some(similar) = self.similar_rule(name) {
+                            report = report.help(format!("did you mean `{similar}`?"));
+                        }
+                        self.reports.push(report);
+                    }
+                }
+            });
+        }
+    }
+
+    // the defined or core rule closest to a misspelled `name`, rulenames are
+    // case-insensitive so casing doesn't count as a difference
+    fn similar_rule(&self, name: &str) -> Option<&'g str> {
+        let name = name.to_ascii_lowercase();
+        let max = (name.len() / 3).max(1);
+
+        self.grammar
+            .rules
+            .iter()
+            .map(|rule| rule.name.as_str())
+            .chain(self.core_rules.iter().map(|core| core.0))
+            .map(|rule| (edit_distance(&name, &rule.to_ascii_lowercase()), rule))
+            .filter(|&(distance, _)| distance <= max)
+            .min()
+            .map(|(_, rule)| rule)
+    }
+}
+
+// Levenshtein distance, the number of single char insertions, deletions and
+// substitutions that turn `a` into `b`
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut previous = (0..=b.len()).collect::<Vec<_>>();
+
+    for (i, a) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a != *b);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::LexerConfig;
+    use crate::core_rules::core_rules;
+    use crate::encoding::Encoding;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    macro_rules! check {
+        {
+            name:   $name:ident,
+            text:   $text:expr,
+            errors: ($($kind:expr),*)
+        } => {
+            #[test]
+            fn $name() {
+                let kinds: &[ReportKind] = &[$($kind,)*];
+
+                test($text, kinds);
+            }
+        }
+    }
+
+    fn test(text: &str, want_report_kinds: &[ReportKind]) {
+        let (tokens, _) = Lexer::new(text, LexerConfig::default(), Encoding::default())
+            .tokenize()
+            .expect("test grammar should lex");
+        let grammar = Parser::new(text, &tokens)
+            .parse()
+            .expect("test grammar should parse");
+
+        let core_rules = core_rules();
+        let have_report_kinds = Checker::new(text, &grammar, Some(&core_rules))
+            .check()
+            .iter()
+            .map(|r| r.get_kind())
+            .collect::<Vec<_>>();
+
+        assert_eq!(have_report_kinds, want_report_kinds);
+    }
+
+    check! {
+        name: valid_grammar,
+        text: "a = b / c\nb = \"b\"\nc = b\nc =/ \"c\"",
+        errors: ()
+    }
+
+    check! {
+        name: undefined_rule,
+        text: "a = b c\nb = \"b\"",
+        errors: (ReportKind::UndefinedRuleError)
+    }
+
+    check! {
+        name: duplicate_rule,
+        text: "a = \"a\"\na = \"b\"",
+        errors: (ReportKind::DuplicateRuleError)
+    }
+
+    check! {
+        name: incremental_before_definition,
+        text: "a = b\nb =/ \"c\"\nb = \"b\"",
+        errors: (ReportKind::IncrementalBeforeDefinitionError)
+    }
+
+    check! {
+        name: incremental_without_definition,
+        text: "a = b\nb =/ \"b\"",
+        errors: (ReportKind::IncrementalBeforeDefinitionError)
+    }
+
+    check! {
+        name: core_rule_reference,
+        text: "a = ALPHA *(DIGIT / HEXDIG)",
+        errors: ()
+    }
+
+    check! {
+        name: core_rule_redefinition,
+        text: "a = DIGIT\nDIGIT = %x30-39",
+        errors: (ReportKind::CoreRuleRedefinitionWarning)
+    }
+
+    fn suggestion(text: &str) -> Vec<String> {
+        let (tokens, _) = Lexer::new(text, LexerConfig::default(), Encoding::default())
+            .tokenize()
+            .unwrap();
+        let grammar = Parser::new(text, &tokens).parse().unwrap();
+
+        let core_rules = core_rules();
+        let reports = Checker::new(text, &grammar, Some(&core_rules)).check();
+        reports[0]
+            .helps()
+            .iter()
+            .map(|help| help.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn did_you_mean() {
+        assert_eq!(
+            suggestion("key = unquoted-key-chr\nunquoted-key-char = ALPHA"),
+            ["did you mean `unquoted-key-char`?"]
+        );
+        assert_eq!(suggestion("a = hexdg"), ["did you mean `HEXDIG`?"]);
+        assert_eq!(suggestion("a = DIGT"), ["did you mean `DIGIT`?"]);
+        assert!(suggestion("a = key\nvalue = ALPHA").is_empty());
+    }
+
+    #[test]
+    fn core_rules_disabled() {
+        let text = "a = ALPHA";
+        let (tokens, _) = Lexer::new(text, LexerConfig::default(), Encoding::default())
+            .tokenize()
+            .unwrap();
+        let grammar = Parser::new(text, &tokens).parse().unwrap();
+
+        let err = Checker::new(text, &grammar, None).check();
+        assert_eq!(err[0].get_kind(), ReportKind::UndefinedRuleError);
+    }
+
+    check! {
+        name: case_insensitive_duplicate,
+        text: "a = \"a\"\nA = \"b\"",
+        errors: (ReportKind::DuplicateRuleError)
+    }
+
+    check! {
+        name: case_insensitive_core_rule_redefinition,
+        text: "a = digit\ndigit = %x30-39",
+        errors: (ReportKind::CoreRuleRedefinitionWarning)
+    }
+}
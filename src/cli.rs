@@ -1,4 +1,4 @@
-use clap::Parser;
+use clap::{Parser, Subcommand, ValueEnum};
 use std::path::PathBuf;
 
 // CLI TODO: Move to own module
@@ -7,11 +7,146 @@ use std::path::PathBuf;
 #[command(author = "Arad Fadaei")]
 #[command(version = "0.1.0")]
 #[command(about = "ABNF grammar toolkit", long_about = None)]
+#[command(subcommand_precedence_over_arg = true)]
 pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
     /// path to abnf file
     pub file: Option<PathBuf>,
 
+    /// apply the fixes of diagnostics that have one, rewriting the file
+    #[arg(long)]
+    pub fix: bool,
+
+    /// print the fixes as a diff instead of rewriting the file
+    #[arg(long, requires = "fix")]
+    pub dry_run: bool,
+
     /// Sets a custom config file
-    #[arg(short, long, value_name = "TOML")]
+    #[arg(short, long, value_name = "TOML", global = true)]
     pub config: Option<PathBuf>,
+
+    /// output format of diagnostics, json also prints the tree of `test`
+    #[arg(long, value_enum, default_value_t = Format::Human, global = true)]
+    pub format: Format,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Test whether an input is in the language of a grammar rule
+    Test {
+        /// path to abnf file
+        grammar: PathBuf,
+
+        /// rule the input has to match
+        #[arg(short, long)]
+        rule: String,
+
+        /// path to input file
+        input: PathBuf,
+
+        /// print how the input matched, as a tree of rules with byte spans
+        #[arg(long)]
+        tree: bool,
+    },
+
+    /// Generate random strings in the language of a grammar rule
+    Generate {
+        /// path to abnf file
+        grammar: PathBuf,
+
+        /// rule to generate strings for
+        #[arg(short, long)]
+        rule: String,
+
+        /// seed for reproducible output, random when not given
+        #[arg(short, long)]
+        seed: Option<u64>,
+
+        /// number of strings to generate
+        #[arg(short = 'n', long, default_value_t = 1)]
+        count: usize,
+
+        /// rule nesting after which rules are finished as quickly as possible
+        #[arg(long, default_value_t = 32)]
+        max_depth: usize,
+
+        /// length after which rules are finished as quickly as possible
+        #[arg(long, default_value_t = 256)]
+        max_length: usize,
+
+        /// weight of a rule when picked as an alternative, defaults to 1
+        #[arg(short, long, value_name = "RULE=WEIGHT", value_parser = parse_weight)]
+        weight: Vec<(String, u32)>,
+
+        /// write every string to its own file in this directory instead of
+        /// printing them one per line
+        #[arg(short, long, value_name = "DIR")]
+        output: Option<PathBuf>,
+    },
+
+    /// Explain a diagnostic code in detail, with an example and how to fix it
+    Explain {
+        /// code of the diagnostic, e.g. A0007
+        code: String,
+    },
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+pub enum Format {
+    Human,
+    Json,
+    /// SARIF 2.1.0, for code scanning dashboards
+    Sarif,
+}
+
+fn parse_weight(arg: &str) -> Result<(String, u32), String> {
+    let (rule, weight) = arg
+        .split_once('=')
+        .ok_or_else(|| format!("expected RULE=WEIGHT, found `{arg}`"))?;
+    let weight = weight
+        .parse::<u32>()
+        .map_err(|err| format!("invalid weight `{weight}`: {err}"))?;
+
+    Ok((rule.to_string(), weight))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Cli {
+        Cli::try_parse_from(std::iter::once("abnf-toolkit").chain(args.iter().copied()))
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    #[test]
+    fn config_before_subcommand() {
+        let cli = parse(&["-c", "cfg.toml", "test", "g.abnf", "-r", "a", "in.txt"]);
+
+        assert_eq!(cli.config, Some(PathBuf::from("cfg.toml")));
+        assert!(cli.file.is_none());
+        assert!(matches!(cli.command, Some(Command::Test { rule, .. }) if rule == "a"));
+    }
+
+    #[test]
+    fn format_before_and_after_subcommand() {
+        let cli = parse(&["--format", "json", "test", "g.abnf", "-r", "a", "in.txt"]);
+        assert!(cli.format == Format::Json);
+        assert!(matches!(cli.command, Some(Command::Test { .. })));
+
+        let cli = parse(&["generate", "g.abnf", "-r", "a", "--format", "sarif"]);
+        assert!(cli.format == Format::Sarif);
+        assert!(matches!(cli.command, Some(Command::Generate { .. })));
+    }
+
+    #[test]
+    fn file_without_subcommand() {
+        let cli = parse(&["--fix", "g.abnf", "-c", "cfg.toml"]);
+
+        assert_eq!(cli.file, Some(PathBuf::from("g.abnf")));
+        assert!(cli.fix);
+        assert!(cli.command.is_none());
+    }
 }
@@ -1,51 +1,101 @@
+use std::collections::HashMap;
 use std::path::PathBuf;
 
 use directories::ProjectDirs;
 use serde::Deserialize;
 
+use crate::encoding::Encoding;
+use crate::lint::{Level, Lint};
+use crate::report_kind::ReportKind;
+use crate::severity::Severity;
+
 #[derive(Deserialize)]
 pub struct Config {
     pub lexer: LexerConfig,
+    #[serde(default)]
+    pub grammar: GrammarConfig,
+    /// severity of report kinds that differs from their default, e.g.
+    /// `UnreachableRuleWarning = "error"`
+    #[serde(default)]
+    pub severity: HashMap<ReportKind, Severity>,
+    /// level of lints that differs from their default, e.g.
+    /// `unused-rule = "allow"`
+    #[serde(default)]
+    pub lint: HashMap<Lint, Level>,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Default, Clone, Copy)]
 pub struct LexerConfig {
     pub extended: bool,
 }
 
-const DEFAULT: &'static str = r#"
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct GrammarConfig {
+    /// make the RFC 5234 Appendix B core rules available to every grammar
+    pub core_rules: bool,
+    /// what terminal values stand for, checked in extended mode
+    pub encoding: Encoding,
+    /// how rule names should be spelled, see `RuleCase`
+    pub rule_case: RuleCase,
+}
+
+/// preferred casing of rule names, references to core rules always follow
+/// the spelling of the core rule
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleCase {
+    /// spelled like the first definition of the rule
+    #[default]
+    Consistent,
+    Lowercase,
+    Uppercase,
+}
+
+impl Default for GrammarConfig {
+    fn default() -> Self {
+        GrammarConfig {
+            core_rules: true,
+            encoding: Encoding::Utf8,
+            rule_case: RuleCase::Consistent,
+        }
+    }
+}
+
+const DEFAULT: &str = r#"
 [lexer]
 extended = false
+
+[grammar]
+core_rules = true
+encoding = "utf-8"
+rule_case = "consistent"
+
+[severity]
+
+[lint]
 "#;
 
 fn compute_config_dir(path: Option<PathBuf>) -> Option<PathBuf> {
     match path {
-        Some(path) => {
-            return Some(path);
-        }
-        None => {
-            if let Some(proj_dir) = ProjectDirs::from("dev", "Arad-Fadaei", "abnf-toolkit") {
-                return Some(proj_dir.config_dir().to_owned());
-            } else {
-                None
-            }
-        }
+        Some(path) => Some(path),
+        None => ProjectDirs::from("dev", "Arad-Fadaei", "abnf-toolkit")
+            .map(|proj_dir| proj_dir.config_dir().to_owned()),
     }
 }
 
 pub fn load_config(path: Option<PathBuf>) -> Config {
     if let Some(dir) = compute_config_dir(path) {
-        let joiner;
-        if dir.is_file() {
-            joiner = dir;
+        let joiner = if dir.is_file() {
+            dir
         } else {
-            joiner = dir.join("config.toml")
-        }
+            dir.join("config.toml")
+        };
 
         let loaded = match std::fs::read_to_string(joiner) {
             Ok(s) => s,
             Err(e) => {
-                println!("{e}");
+                eprintln!("{e}");
 
                 DEFAULT.to_string()
             }
@@ -0,0 +1,62 @@
+use crate::config::LexerConfig;
+use crate::encoding::Encoding;
+use crate::grammar::Grammar;
+use crate::lexer::Lexer;
+use crate::parser::Parser;
+
+/// RFC 5234 Appendix B.1, implicitly available to every grammar
+const CORE_RULES: &str = r#"
+ALPHA  = %x41-5A / %x61-7A ; A-Z / a-z
+BIT    = "0" / "1"
+CHAR   = %x01-7F ; any 7-bit US-ASCII character, excluding NUL
+CR     = %x0D ; carriage return
+CRLF   = CR LF ; Internet standard newline
+CTL    = %x00-1F / %x7F ; controls
+DIGIT  = %x30-39 ; 0-9
+DQUOTE = %x22 ; " (Double Quote)
+HEXDIG = DIGIT / "A" / "B" / "C" / "D" / "E" / "F"
+HTAB   = %x09 ; horizontal tab
+LF     = %x0A ; linefeed
+LWSP   = *(WSP / CRLF WSP) ; linear white space (past newline)
+OCTET  = %x00-FF ; 8 bits of data
+SP     = %x20
+VCHAR  = %x21-7E ; visible (printing) characters
+WSP    = SP / HTAB ; white space
+"#;
+
+pub fn core_rules() -> Grammar {
+    // core rules go beyond 7E, so they always need the extended lexer
+    let (tokens, _) = Lexer::new(
+        CORE_RULES,
+        LexerConfig { extended: true },
+        Encoding::default(),
+    )
+    .tokenize()
+    .expect("core rules should lex");
+
+    Parser::new(CORE_RULES, &tokens)
+        .parse()
+        .expect("core rules should parse")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defines_appendix_b() {
+        let names = core_rules()
+            .rules
+            .into_iter()
+            .map(|r| r.name)
+            .collect::<Vec<String>>();
+
+        assert_eq!(
+            names,
+            [
+                "ALPHA", "BIT", "CHAR", "CR", "CRLF", "CTL", "DIGIT", "DQUOTE", "HEXDIG", "HTAB",
+                "LF", "LWSP", "OCTET", "SP", "VCHAR", "WSP"
+            ]
+        );
+    }
+}
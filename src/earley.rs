@@ -0,0 +1,446 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::ops::RangeInclusive;
+
+use crate::encoding::Encoding;
+use crate::expr::{Expr, ExprKind, NumVal};
+use crate::grammar::Name;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Symbol {
+    Nonterminal(usize),
+    Terminal(usize),
+}
+
+pub struct Nonterminal<'g> {
+    /// `None` for the nonterminals introduced for groups, options and repetitions
+    pub name: Option<&'g str>,
+    pub productions: Vec<usize>,
+    pub nullable: bool,
+}
+
+pub struct Production {
+    pub lhs: usize,
+    pub rhs: Vec<Symbol>,
+}
+
+pub struct Terminal {
+    ranges: Vec<RangeInclusive<u32>>,
+    pub description: String,
+}
+
+impl Terminal {
+    pub fn matches(&self, value: u32) -> bool {
+        self.ranges.iter().any(|range| range.contains(&value))
+    }
+}
+
+/// a grammar flattened into plain productions for the Earley recognizer
+///
+/// groups, options and repetitions become nonterminals of their own, built so
+/// they never add derivations the ABNF itself does not have
+pub struct EarleyGrammar<'g> {
+    pub nonterminals: Vec<Nonterminal<'g>>,
+    pub productions: Vec<Production>,
+    pub terminals: Vec<Terminal>,
+
+    rules: HashMap<Name<'g>, usize>,
+    // nonterminals standing in for prose, they never match
+    prose: HashSet<usize>,
+    // how the chars of quoted strings become values
+    encoding: Encoding,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Item {
+    pub production: usize,
+    pub dot: usize,
+    pub origin: usize,
+}
+
+/// one Earley set per input position, plus one for the end of the input
+pub struct Chart {
+    pub sets: Vec<Vec<Item>>,
+    contains: Vec<HashSet<Item>>,
+    // items in a set waiting on a nonterminal
+    waiting: Vec<HashMap<usize, Vec<Item>>>,
+    // (nonterminal, origin) of every completed item in a set
+    completed: Vec<HashSet<(usize, usize)>>,
+}
+
+impl<'g> EarleyGrammar<'g> {
+    pub fn new(rules: &HashMap<Name<'g>, Vec<&'g Expr>>, encoding: Encoding) -> Self {
+        let mut grammar = EarleyGrammar {
+            nonterminals: Vec::new(),
+            productions: Vec::new(),
+            terminals: Vec::new(),
+            rules: HashMap::new(),
+            prose: HashSet::new(),
+            encoding,
+        };
+
+        // every rule needs a nonterminal before any reference to it is compiled
+        for &name in rules.keys() {
+            let nonterminal = grammar.nonterminal(Some(name.0));
+            grammar.rules.insert(name, nonterminal);
+        }
+
+        for (name, exprs) in rules {
+            let nonterminal = grammar.rules[name];
+            for expr in exprs {
+                grammar.add_alternatives(nonterminal, expr);
+            }
+        }
+
+        grammar.compute_nullable();
+
+        grammar
+    }
+
+    pub fn rule(&self, name: &str) -> Option<usize> {
+        self.rules.get(&Name(name)).copied()
+    }
+
+    pub fn recognize(&self, start: usize, input: &[u32]) -> Chart {
+        let mut chart = Chart {
+            sets: vec![Vec::new(); input.len() + 1],
+            contains: vec![HashSet::new(); input.len() + 1],
+            waiting: vec![HashMap::new(); input.len() + 1],
+            completed: vec![HashSet::new(); input.len() + 1],
+        };
+
+        for &production in &self.nonterminals[start].productions {
+            self.add(&mut chart, 0, Item::new(production, 0));
+        }
+
+        for i in 0..=input.len() {
+            let mut next = 0;
+            while next < chart.sets[i].len() {
+                let item = chart.sets[i][next];
+                next += 1;
+
+                let production = &self.productions[item.production];
+                match production.rhs.get(item.dot) {
+                    // complete
+                    None => {
+                        chart.completed[i].insert((production.lhs, item.origin));
+
+                        let waiting = chart.waiting[item.origin]
+                            .get(&production.lhs)
+                            .cloned()
+                            .unwrap_or_default();
+                        for waiting in waiting {
+                            self.add(&mut chart, i, waiting.advance());
+                        }
+                    }
+                    // predict, nullable nonterminals are skipped over right
+                    // away as their completion may already have happened
+                    Some(Symbol::Nonterminal(nonterminal)) => {
+                        for &production in &self.nonterminals[*nonterminal].productions {
+                            self.add(&mut chart, i, Item::new(production, i));
+                        }
+                        if self.nonterminals[*nonterminal].nullable {
+                            self.add(&mut chart, i, item.advance());
+                        }
+                    }
+                    // scan
+                    Some(Symbol::Terminal(terminal)) => {
+                        if input
+                            .get(i)
+                            .is_some_and(|&c| self.terminals[*terminal].matches(c))
+                        {
+                            self.add(&mut chart, i + 1, item.advance());
+                        }
+                    }
+                }
+            }
+        }
+
+        chart
+    }
+
+    /// whether prose is used by `start` or any rule it uses
+    pub fn reaches_prose(&self, start: usize) -> bool {
+        let mut seen = HashSet::from([start]);
+        let mut stack = vec![start];
+
+        while let Some(nonterminal) = stack.pop() {
+            if self.prose.contains(&nonterminal) {
+                return true;
+            }
+
+            for &production in &self.nonterminals[nonterminal].productions {
+                for symbol in &self.productions[production].rhs {
+                    if let Symbol::Nonterminal(next) = *symbol {
+                        if seen.insert(next) {
+                            stack.push(next);
+                        }
+                    }
+                }
+            }
+        }
+
+        false
+    }
+
+    /// descriptions of the terminals the items of a set are waiting on
+    pub fn expected(&self, chart: &Chart, set: usize) -> BTreeSet<String> {
+        chart.sets[set]
+            .iter()
+            .filter_map(
+                |item| match self.productions[item.production].rhs.get(item.dot) {
+                    Some(Symbol::Terminal(terminal)) => {
+                        Some(self.terminals[*terminal].description.clone())
+                    }
+                    _ => None,
+                },
+            )
+            .collect()
+    }
+
+    /// whether `symbol` derives `input[start..end]`, according to the chart
+    pub fn derives(
+        &self,
+        chart: &Chart,
+        input: &[u32],
+        symbol: Symbol,
+        start: usize,
+        end: usize,
+    ) -> bool {
+        match symbol {
+            Symbol::Terminal(terminal) => {
+                end == start + 1 && self.terminals[terminal].matches(input[start])
+            }
+            Symbol::Nonterminal(nonterminal) => {
+                chart.completed[end].contains(&(nonterminal, start))
+            }
+        }
+    }
+
+    fn add(&self, chart: &mut Chart, set: usize, item: Item) {
+        if !chart.contains[set].insert(item) {
+            return;
+        }
+
+        if let Some(Symbol::Nonterminal(nonterminal)) =
+            self.productions[item.production].rhs.get(item.dot)
+        {
+            chart.waiting[set]
+                .entry(*nonterminal)
+                .or_default()
+                .push(item);
+        }
+        chart.sets[set].push(item);
+    }
+
+    fn nonterminal(&mut self, name: Option<&'g str>) -> usize {
+        self.nonterminals.push(Nonterminal {
+            name,
+            productions: Vec::new(),
+            nullable: false,
+        });
+        self.nonterminals.len() - 1
+    }
+
+    fn add_production(&mut self, lhs: usize, rhs: Vec<Symbol>) {
+        self.productions.push(Production { lhs, rhs });
+        self.nonterminals[lhs]
+            .productions
+            .push(self.productions.len() - 1);
+    }
+
+    fn terminal(&mut self, ranges: Vec<RangeInclusive<u32>>, description: String) -> Symbol {
+        self.terminals.push(Terminal {
+            ranges,
+            description,
+        });
+        Symbol::Terminal(self.terminals.len() - 1)
+    }
+
+    // every alternative of `expr` becomes a production of `lhs`
+    fn add_alternatives(&mut self, lhs: usize, expr: &Expr) {
+        match &expr.kind {
+            ExprKind::Alternation(alternatives) => {
+                for alternative in alternatives {
+                    let rhs = self.sequence(alternative);
+                    self.add_production(lhs, rhs);
+                }
+            }
+            _ => {
+                let rhs = self.sequence(expr);
+                self.add_production(lhs, rhs);
+            }
+        }
+    }
+
+    // the symbols matching `expr`, one after the other
+    fn sequence(&mut self, expr: &Expr) -> Vec<Symbol> {
+        match &expr.kind {
+            ExprKind::Alternation(_) => vec![self.anonymous(expr)],
+            ExprKind::Concatenation(items) => {
+                items.iter().flat_map(|item| self.sequence(item)).collect()
+            }
+            ExprKind::Repetition { min, max, expr } => {
+                let item = self.symbol(expr);
+                let mut symbols = vec![item; *min];
+
+                match max {
+                    // star = "" / item star
+                    None => {
+                        let star = self.nonterminal(None);
+                        self.add_production(star, Vec::new());
+                        self.add_production(star, vec![item, Symbol::Nonterminal(star)]);
+                        symbols.push(Symbol::Nonterminal(star));
+                    }
+                    // nested options, [item [item [item]]], rather than
+                    // [item] [item] [item] which is ambiguous
+                    Some(max) if max > min => {
+                        let mut tail: Option<Symbol> = None;
+                        for _ in *min..*max {
+                            let optional = self.nonterminal(None);
+                            self.add_production(optional, Vec::new());
+                            self.add_production(optional, [item].into_iter().chain(tail).collect());
+                            tail = Some(Symbol::Nonterminal(optional));
+                        }
+                        symbols.extend(tail);
+                    }
+                    Some(_) => {}
+                }
+
+                symbols
+            }
+            ExprKind::Group(inner) => self.sequence(inner),
+            ExprKind::Option(inner) => {
+                let optional = self.nonterminal(None);
+                self.add_production(optional, Vec::new());
+                self.add_alternatives(optional, inner);
+                vec![Symbol::Nonterminal(optional)]
+            }
+            ExprKind::Rule(name) => match self.rules.get(&Name(name)) {
+                Some(&nonterminal) => vec![Symbol::Nonterminal(nonterminal)],
+                // an undefined rule has no productions and never matches
+                None => vec![Symbol::Nonterminal(self.nonterminal(None))],
+            },
+            // only ASCII letters have another case, other chars are matched
+            // as they are encoded
+            ExprKind::CharVal {
+                value,
+                case_sensitive,
+            } => {
+                let mut symbols = Vec::new();
+                for c in value.chars() {
+                    let description = match case_sensitive {
+                        true => format!("%s\"{c}\""),
+                        false => format!("\"{c}\""),
+                    };
+
+                    if !case_sensitive && c.is_ascii_alphabetic() {
+                        let lower = c.to_ascii_lowercase() as u32;
+                        let upper = c.to_ascii_uppercase() as u32;
+                        symbols
+                            .push(self.terminal(vec![lower..=lower, upper..=upper], description));
+                    } else {
+                        for unit in self.encoding.encode_char(c) {
+                            symbols.push(self.terminal(vec![unit..=unit], description.clone()));
+                        }
+                    }
+                }
+                symbols
+            }
+            ExprKind::NumVal { radix, value } => match value {
+                NumVal::Range(min, max) => {
+                    vec![self.terminal(vec![*min..=*max], expr.to_string())]
+                }
+                NumVal::Sequence(values) => values
+                    .iter()
+                    .map(|&value| {
+                        let single = Expr::new(
+                            ExprKind::NumVal {
+                                radix: radix.clone(),
+                                value: NumVal::Sequence(vec![value]),
+                            },
+                            expr.span,
+                        );
+                        self.terminal(vec![value..=value], single.to_string())
+                    })
+                    .collect(),
+            },
+            // prose can't be matched, like an undefined rule
+            ExprKind::ProseVal(_) => {
+                let prose = self.nonterminal(None);
+                self.prose.insert(prose);
+                vec![Symbol::Nonterminal(prose)]
+            }
+        }
+    }
+
+    // a single symbol matching `expr`
+    fn symbol(&mut self, expr: &Expr) -> Symbol {
+        let mut symbols = self.sequence(expr);
+        if symbols.len() == 1 {
+            return symbols.remove(0);
+        }
+
+        let nonterminal = self.nonterminal(None);
+        self.add_production(nonterminal, symbols);
+        Symbol::Nonterminal(nonterminal)
+    }
+
+    fn anonymous(&mut self, expr: &Expr) -> Symbol {
+        let nonterminal = self.nonterminal(None);
+        self.add_alternatives(nonterminal, expr);
+        Symbol::Nonterminal(nonterminal)
+    }
+
+    fn compute_nullable(&mut self) {
+        let mut changed = true;
+        while changed {
+            changed = false;
+
+            for production in &self.productions {
+                if self.nonterminals[production.lhs].nullable {
+                    continue;
+                }
+
+                let nullable = production.rhs.iter().all(|symbol| match symbol {
+                    Symbol::Nonterminal(nonterminal) => self.nonterminals[*nonterminal].nullable,
+                    Symbol::Terminal(_) => false,
+                });
+                if nullable {
+                    self.nonterminals[production.lhs].nullable = true;
+                    changed = true;
+                }
+            }
+        }
+    }
+}
+
+impl Item {
+    fn new(production: usize, origin: usize) -> Self {
+        Item {
+            production,
+            dot: 0,
+            origin,
+        }
+    }
+
+    fn advance(&self) -> Self {
+        Item {
+            dot: self.dot + 1,
+            ..*self
+        }
+    }
+}
+
+impl Chart {
+    pub fn contains(&self, set: usize, item: Item) -> bool {
+        self.contains[set].contains(&item)
+    }
+
+    /// the last set with any items, how far into the input the recognizer got
+    pub fn furthest(&self) -> usize {
+        self.sets
+            .iter()
+            .rposition(|set| !set.is_empty())
+            .unwrap_or(0)
+    }
+}
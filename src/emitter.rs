@@ -0,0 +1,300 @@
+use crate::cli::Format;
+use crate::explain::explain;
+use crate::fix::Edit;
+use crate::report::Report;
+use crate::report_kind::ReportKind;
+use crate::severity::Severity;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::Path;
+use strum::IntoEnumIterator;
+
+/// prints the reports of a run, human reports right away and machine
+/// readable ones as a single document once the run is done
+pub struct Emitter {
+    format: Format,
+    // overrides from the config, applied to every report
+    severity: HashMap<ReportKind, Severity>,
+    diagnostics: Vec<Value>,
+    errors: usize,
+    // fixes of every report, for `--fix`
+    edits: Vec<Edit>,
+}
+
+impl Emitter {
+    pub fn new(format: Format, severity: HashMap<ReportKind, Severity>) -> Self {
+        Emitter {
+            format,
+            severity,
+            diagnostics: Vec::new(),
+            errors: 0,
+            edits: Vec::new(),
+        }
+    }
+
+    /// whether any report emitted so far is an error
+    pub fn has_errors(&self) -> bool {
+        self.errors > 0
+    }
+
+    /// the fixes of the reports emitted so far
+    pub fn edits(&self) -> &[Edit] {
+        &self.edits
+    }
+
+    pub fn is_human(&self) -> bool {
+        self.format == Format::Human
+    }
+
+    /// reports of the lexer, parser or checker for the grammar at `path`
+    pub fn reports(&mut self, path: &Path, mut reports: Vec<Report>) {
+        reports
+            .iter_mut()
+            .for_each(|report| self.override_severity(report));
+        let errors = reports
+            .iter()
+            .any(|report| report.severity() == Severity::Error);
+        if self.format == Format::Human && errors {
+            println!("-The following syntax errors where found:-");
+        }
+
+        for report in reports {
+            self.report(path, report);
+        }
+    }
+
+    /// a single report about the file at `path`
+    pub fn report(&mut self, path: &Path, mut report: Report) {
+        self.override_severity(&mut report);
+        if report.severity() == Severity::Error {
+            self.errors += 1;
+        }
+        if let Some(edit) = report.edit() {
+            self.edits.push(edit.clone());
+        }
+
+        match self.format {
+            Format::Human => println!("{report}"),
+            Format::Json | Format::Sarif => self.diagnostics.push(diagnostic(path, &report)),
+        }
+    }
+
+    fn override_severity(&self, report: &mut Report) {
+        if let Some(&severity) = self.severity.get(&report.get_kind()) {
+            report.set_severity(severity);
+        }
+    }
+
+    /// write all collected reports, nothing for human output
+    pub fn finish(self, out: &mut dyn Write) -> std::io::Result<()> {
+        let document = match self.format {
+            Format::Human => return Ok(()),
+            Format::Json => Value::Array(self.diagnostics),
+            Format::Sarif => sarif(&self.diagnostics),
+        };
+
+        let document = serde_json::to_string_pretty(&document).expect("json always serializes");
+        writeln!(out, "{document}")
+    }
+}
+
+// line and column are 1 based and columns count chars, span is a byte range
+// into the file
+fn diagnostic(path: &Path, report: &Report) -> Value {
+    let kind = report.get_kind();
+    let pos = report.pos();
+
+    json!({
+        "file": path.display().to_string(),
+        "line": pos.map(|pos| pos.line),
+        "column": pos.map(|pos| pos.column),
+        "span": report.span().map(|span| json!({ "start": span.start, "end": span.end })),
+        "severity": report.severity().to_string(),
+        "code": kind.code(),
+        "kind": kind.to_string(),
+        "message": report.msg(),
+        "notes": report.notes(),
+        "help": report.helps(),
+        "fix": report.edit().map(|edit| json!({
+            "span": { "start": edit.span.start, "end": edit.span.end },
+            "replacement": edit.replacement,
+        })),
+    })
+}
+
+// https://docs.oasis-open.org/sarif/sarif/v2.1.0/sarif-v2.1.0.html
+fn sarif(diagnostics: &[Value]) -> Value {
+    let rules = ReportKind::iter()
+        .map(|kind| {
+            json!({
+                "id": kind.code(),
+                "name": kind.to_string(),
+                "shortDescription": { "text": kind.msg() },
+                "fullDescription": { "text": explain(&kind).description },
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let results = diagnostics
+        .iter()
+        .map(|diagnostic| {
+            let mut text = diagnostic["message"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string();
+            let notes = diagnostic["notes"].as_array().into_iter().flatten();
+            let helps = diagnostic["help"].as_array().into_iter().flatten();
+            for (label, note) in notes.map(|n| ("note", n)).chain(helps.map(|n| ("help", n))) {
+                text.push_str(&format!("\n{label}: {}", note.as_str().unwrap_or_default()));
+            }
+
+            let mut location = json!({
+                "physicalLocation": {
+                    "artifactLocation": { "uri": diagnostic["file"] },
+                }
+            });
+            if !diagnostic["line"].is_null() {
+                let span = &diagnostic["span"];
+                let length =
+                    span["end"].as_u64().unwrap_or(0) - span["start"].as_u64().unwrap_or(0);
+
+                location["physicalLocation"]["region"] = json!({
+                    "startLine": diagnostic["line"],
+                    "startColumn": diagnostic["column"],
+                    "byteOffset": span["start"],
+                    "byteLength": length,
+                });
+            }
+
+            let mut result = json!({
+                "ruleId": diagnostic["code"],
+                // sarif has no help level
+                "level": match diagnostic["severity"].as_str() {
+                    Some("help") => "note",
+                    level => level.unwrap_or("error"),
+                },
+                "message": { "text": text },
+                "locations": [location],
+            });
+            let fix = &diagnostic["fix"];
+            if !fix.is_null() {
+                let span = &fix["span"];
+                let length =
+                    span["end"].as_u64().unwrap_or(0) - span["start"].as_u64().unwrap_or(0);
+
+                result["fixes"] = json!([{
+                    "artifactChanges": [{
+                        "artifactLocation": { "uri": diagnostic["file"] },
+                        "replacements": [{
+                            "deletedRegion": { "byteOffset": span["start"], "byteLength": length },
+                            "insertedContent": { "text": fix["replacement"] },
+                        }],
+                    }],
+                }]);
+            }
+
+            result
+        })
+        .collect::<Vec<_>>();
+
+    json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "abnf-toolkit",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": "https://github.com/fadaei-dev/abnf-toolkit",
+                    "rules": rules,
+                }
+            },
+            "columnKind": "unicodeCodePoints",
+            "results": results,
+        }]
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::span::Span;
+
+    // the prose starts after a two byte char, so its column and byte offset
+    // differ
+    const SRC: &str = "a = b\nc = \"é\" / <x>\n";
+
+    fn prose() -> Report {
+        Report::from_span(ReportKind::ProseValWarning, SRC, Span::new(17, 20))
+            .note("found prose")
+            .help("describe it with rules")
+            .fix(Span::new(17, 20), "\"x\"")
+    }
+
+    #[test]
+    fn json_diagnostic() {
+        let diagnostic = diagnostic(Path::new("g.abnf"), &prose());
+
+        assert_eq!(
+            diagnostic,
+            json!({
+                "file": "g.abnf",
+                "line": 2,
+                "column": 11,
+                "span": { "start": 17, "end": 20 },
+                "severity": "warning",
+                "code": "A0037",
+                "kind": "ProseValWarning",
+                "message": ReportKind::ProseValWarning.msg(),
+                "notes": ["found prose"],
+                "help": ["describe it with rules"],
+                "fix": {
+                    "span": { "start": 17, "end": 20 },
+                    "replacement": "\"x\"",
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn sarif_results() {
+        let mut help = prose();
+        help.set_severity(Severity::Help);
+        let path = Path::new("g.abnf");
+        let document = sarif(&[diagnostic(path, &prose()), diagnostic(path, &help)]);
+
+        let run = &document["runs"][0];
+        assert_eq!(run["columnKind"], "unicodeCodePoints");
+
+        let results = run["results"].as_array().unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0]["ruleId"], "A0037");
+        assert_eq!(results[0]["level"], "warning");
+        // sarif has no help level
+        assert_eq!(results[1]["level"], "note");
+        assert_eq!(
+            results[0]["message"]["text"],
+            format!(
+                "{}\nnote: found prose\nhelp: describe it with rules",
+                ReportKind::ProseValWarning.msg()
+            )
+        );
+
+        let location = &results[0]["locations"][0]["physicalLocation"];
+        assert_eq!(location["artifactLocation"]["uri"], "g.abnf");
+        assert_eq!(
+            location["region"],
+            json!({ "startLine": 2, "startColumn": 11, "byteOffset": 17, "byteLength": 3 })
+        );
+
+        let replacement = &results[0]["fixes"][0]["artifactChanges"][0]["replacements"][0];
+        assert_eq!(
+            replacement,
+            &json!({
+                "deletedRegion": { "byteOffset": 17, "byteLength": 3 },
+                "insertedContent": { "text": "\"x\"" },
+            })
+        );
+    }
+}
@@ -0,0 +1,101 @@
+use serde::Deserialize;
+
+/// what the integers of terminals stand for, and how input and output are
+/// turned into them
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+pub enum Encoding {
+    /// octets, for binary wire formats
+    #[serde(rename = "bytes")]
+    Bytes,
+    /// Unicode code points, input and output are UTF-8
+    #[default]
+    #[serde(rename = "utf-8")]
+    Utf8,
+    /// UTF-16 code units, input and output are UTF-16LE, a BOM picks the byte order
+    #[serde(rename = "utf-16")]
+    Utf16,
+}
+
+impl Encoding {
+    pub fn max_value(self) -> u32 {
+        match self {
+            Encoding::Bytes => 0xFF,
+            Encoding::Utf8 => 0x10FFFF,
+            Encoding::Utf16 => 0xFFFF,
+        }
+    }
+
+    /// the values a char of a quoted string stands for
+    pub fn encode_char(self, c: char) -> Vec<u32> {
+        match self {
+            Encoding::Bytes => c.to_string().bytes().map(u32::from).collect(),
+            Encoding::Utf8 => vec![c as u32],
+            Encoding::Utf16 => c
+                .encode_utf16(&mut [0; 2])
+                .iter()
+                .map(|&unit| unit as u32)
+                .collect(),
+        }
+    }
+
+    /// split input into values and the byte offset each starts at, plus the
+    /// end of the input, or the byte offset where the input is invalid
+    pub fn decode(self, input: &[u8]) -> Result<(Vec<u32>, Vec<usize>), usize> {
+        let (values, mut offsets): (Vec<u32>, Vec<usize>) = match self {
+            Encoding::Bytes => input
+                .iter()
+                .enumerate()
+                .map(|(i, &b)| (b as u32, i))
+                .unzip(),
+            Encoding::Utf8 => std::str::from_utf8(input)
+                .map_err(|err| err.valid_up_to())?
+                .char_indices()
+                .map(|(i, c)| (c as u32, i))
+                .unzip(),
+            Encoding::Utf16 => {
+                let (big_endian, start) = match input {
+                    [0xFE, 0xFF, ..] => (true, 2),
+                    [0xFF, 0xFE, ..] => (false, 2),
+                    _ => (false, 0),
+                };
+                if !(input.len() - start).is_multiple_of(2) {
+                    return Err(input.len() - 1);
+                }
+
+                input[start..]
+                    .chunks(2)
+                    .enumerate()
+                    .map(|(i, pair)| {
+                        let pair = [pair[0], pair[1]];
+                        let unit = if big_endian {
+                            u16::from_be_bytes(pair)
+                        } else {
+                            u16::from_le_bytes(pair)
+                        };
+                        (unit as u32, start + i * 2)
+                    })
+                    .unzip()
+            }
+        };
+
+        offsets.push(input.len());
+        Ok((values, offsets))
+    }
+
+    /// turn values back into output, values that can't be encoded become
+    /// U+FFFD in UTF-8
+    pub fn encode(self, values: &[u32]) -> Vec<u8> {
+        match self {
+            Encoding::Bytes => values.iter().map(|&value| value as u8).collect(),
+            Encoding::Utf8 => values
+                .iter()
+                .map(|&value| char::from_u32(value).unwrap_or(char::REPLACEMENT_CHARACTER))
+                .collect::<String>()
+                .into_bytes(),
+            Encoding::Utf16 => values
+                .iter()
+                .flat_map(|&value| (value as u16).to_le_bytes())
+                .collect(),
+        }
+    }
+}
@@ -0,0 +1,471 @@
+use crate::report_kind::ReportKind;
+
+/// long form description of a report kind, printed by `explain`
+pub struct Explanation {
+    pub description: &'static str,
+    /// a grammar that causes the report and the same grammar fixed
+    pub example: Option<(&'static str, &'static str)>,
+    pub section: Option<&'static str>,
+}
+
+impl std::fmt::Display for Explanation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{}", self.description)?;
+
+        if let Some((bad, fixed)) = self.example {
+            writeln!(f, "\nerroneous example:\n\n{}", indent(bad))?;
+            writeln!(f, "\nfixed example:\n\n{}", indent(fixed))?;
+        }
+        if let Some(section) = self.section {
+            writeln!(f, "\nsee {section}")?;
+        }
+
+        Ok(())
+    }
+}
+
+fn indent(example: &str) -> String {
+    example
+        .lines()
+        .map(|line| format!("    {line}"))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub fn explain(kind: &ReportKind) -> Explanation {
+    use ReportKind::*;
+
+    let (description, example, section) = match kind {
+        UnexpectedCharacterError => (
+            "A character was found that can not start any ABNF token, such as `@`, `_` or\n\
+            `>` outside of prose. Lexing skips the character and goes on.",
+            Some(("key = name_part", "key = name-part\nname-part = 1*ALPHA")),
+            Some("RFC 5234 section 4"),
+        ),
+        UnableToAdvanceError => (
+            "The lexer tried to read past the end of the file. This is a bug in the\n\
+            lexer, please report it along with the grammar.",
+            None,
+            None,
+        ),
+        InternalLexerError => (
+            "The lexer reached a state it should never be in, for example a `\\r` that\n\
+            is not followed by `\\n`. Make sure lines end in CRLF or LF, otherwise\n\
+            please report it along with the grammar.",
+            None,
+            Some("RFC 5234 section 2.2"),
+        ),
+        EofError => (
+            "The file ended in the middle of an element, for example right after the\n\
+            `%` of a numeric value in a file that does not end in a newline.",
+            Some(("octal = %", "octal = %x30-37")),
+            Some("RFC 5234 section 2.3"),
+        ),
+        UnclosedBracketError => (
+            "A `(` or `[` is never closed. Groups and options have to be closed before\n\
+            the rule ends.",
+            Some((
+                "list = item *(\",\" item",
+                "list = item *(\",\" item)\nitem = 1*ALPHA",
+            )),
+            Some("RFC 5234 sections 3.5 and 3.8"),
+        ),
+        MismatchedClosingBracketError => (
+            "A bracket is closed with the other kind of bracket, a group opened with\n\
+            `(` has to be closed with `)` and an option opened with `[` with `]`.",
+            Some((
+                "number = (\"+\" / \"-\"] 1*DIGIT",
+                "number = [\"+\" / \"-\"] 1*DIGIT",
+            )),
+            Some("RFC 5234 sections 3.5 and 3.8"),
+        ),
+        UnexpectedClosingBracketError => (
+            "A `)` or `]` closes a bracket that was never opened.",
+            Some((
+                "number = \"+\" / \"-\") 1*DIGIT",
+                "number = (\"+\" / \"-\") 1*DIGIT",
+            )),
+            Some("RFC 5234 sections 3.5 and 3.8"),
+        ),
+        NoTerminalFoundError => (
+            "A `%` is followed by whitespace instead of the base of a numeric value\n\
+            (b, d or x) or the case of a string (s or i).",
+            Some(("octal = % x30-37", "octal = %x30-37")),
+            Some("RFC 5234 section 2.3"),
+        ),
+        IncorrectTerminalFoundError => (
+            "A `%` is followed by something other than the base of a numeric value\n\
+            (b, d or x) or the case of a string (s or i).",
+            Some(("octal = %h30-37", "octal = %x30-37")),
+            Some("RFC 5234 section 2.3"),
+        ),
+        BinaryTerminalError => (
+            "A binary value contains a digit other than 0 or 1.",
+            Some(("binary-digit = %b2", "binary-digit = %b0110000 / %b0110001")),
+            Some("RFC 5234 section 2.3"),
+        ),
+        SevenBitsError => (
+            "Binary values are 7 bit US-ASCII characters. Set `extended = true` in the\n\
+            `[lexer]` table of the config to allow any width.",
+            Some(("a = %b1100001000", "a = %b1100001")),
+            Some("RFC 5234 section 2.3"),
+        ),
+        DecimalTerminalError => (
+            "Decimal values are US-ASCII characters up to 126. Set `extended = true` in\n\
+            the `[lexer]` table of the config to allow larger values.",
+            Some(("e-acute = %d233", "e = %d101")),
+            Some("RFC 5234 section 2.3"),
+        ),
+        HexadecimalTerminalError => (
+            "Hexadecimal values are US-ASCII characters up to 7E. Set `extended = true`\n\
+            in the `[lexer]` table of the config to allow larger values.",
+            Some(("e-acute = %xE9", "e = %x65")),
+            Some("RFC 5234 section 2.3"),
+        ),
+        NaHexNError => (
+            "Hexadecimal values are two hexadecimal digits, any number of digits in\n\
+            extended mode.",
+            Some(("newline = %xA", "newline = %x0A")),
+            Some("RFC 5234 section 2.3"),
+        ),
+        TerminalOverflowError => (
+            "In extended mode a numeric value does not fit in 32 bits, no encoding has\n\
+            values that large.",
+            Some(("big = %x1FFFFFFFF", "big = %x10FFFF")),
+            Some("RFC 5234 section 2.3"),
+        ),
+        SurrogateTerminalError => (
+            "In extended mode with the utf-8 encoding values are Unicode code points,\n\
+            D800-DFFF are surrogates which only exist in UTF-16 and are never\n\
+            characters. Match the character instead, or set `encoding = \"utf-16\"` in\n\
+            the `[grammar]` table to match code units.",
+            Some(("emoji = %xD83D.DE00", "emoji = %x1F600")),
+            Some("RFC 5234 section 2.4"),
+        ),
+        TerminalRangeError => (
+            "In extended mode a numeric value is larger than any value of the grammar's\n\
+            encoding, FF for bytes, FFFF for utf-16 and 10FFFF for utf-8.",
+            Some(("astral = %x10000-110000", "astral = %x10000-10FFFF")),
+            Some("RFC 5234 section 2.4"),
+        ),
+        ExpectedStringError => (
+            "%s and %i set the case sensitivity of a quoted string and have to be\n\
+            followed by one directly.",
+            Some(("get = %s GET", "get = %s\"GET\"")),
+            Some("RFC 7405 section 2.1"),
+        ),
+        UnterminatedStringError => (
+            "A quoted string has to be closed with `\"` on the same line.",
+            Some(("get = \"GET", "get = \"GET\"")),
+            Some("RFC 5234 section 2.3"),
+        ),
+        StringCharacterError => (
+            "Quoted strings may only contain printable US-ASCII characters other than\n\
+            `\"`. Use a numeric value for other characters, extended mode also allows\n\
+            non-ASCII characters.",
+            Some(("tab = \"\t\"", "tab = %x09")),
+            Some("RFC 5234 section 2.3"),
+        ),
+        UnterminatedProseError => (
+            "A prose value has to be closed with `>` on the same line.",
+            Some((
+                "date = <a date as in RFC 3339",
+                "; abnf-toolkit: allow(prose-val)\ndate = <a date as in RFC 3339>",
+            )),
+            Some("RFC 5234 section 4"),
+        ),
+        ProseCharacterError => (
+            "Prose values may only contain printable US-ASCII characters other than `>`.",
+            Some((
+                "name = <na\u{ef}ve name>",
+                "; abnf-toolkit: allow(prose-val)\nname = <naive name>",
+            )),
+            Some("RFC 5234 section 4"),
+        ),
+        NaNError => (
+            "A number was expected, after the base of a numeric value or in a repeat\n\
+            prefix, but it is missing or too large.",
+            Some(("pair = %d.49", "pair = %d48.49")),
+            Some("RFC 5234 sections 2.3 and 3.6"),
+        ),
+        RepeatWhitespaceError => (
+            "A repeat prefix has to be written without whitespace and directly in front\n\
+            of the element it repeats.",
+            Some(("list = 1 * item", "list = 1*item\nitem = 1*ALPHA")),
+            Some("RFC 5234 section 3.6"),
+        ),
+        InvertedRepeatError => (
+            "The minimum of a repeat prefix is greater than its maximum, so the element\n\
+            can never be repeated a valid number of times.",
+            Some(("hex = 4*2HEXDIG", "hex = 2*4HEXDIG")),
+            Some("RFC 5234 section 3.6"),
+        ),
+        ExpectedRuleNameError => (
+            "A rule has to start with its name in the first column, lines of a rule\n\
+            after the first have to be indented.",
+            Some(("\"a\" = b", "a = \"a\"")),
+            Some("RFC 5234 section 2.2"),
+        ),
+        ExpectedDefinedAsError => (
+            "The name of a rule has to be followed by `=` or `=/`.",
+            Some(("octal %x30-37", "octal = %x30-37")),
+            Some("RFC 5234 sections 2.2 and 3.3"),
+        ),
+        ExpectedElementError => (
+            "An element was expected, a rule name, group, option, string, numeric value\n\
+            or prose, but something else was found, for example an empty alternative.",
+            Some(("sign = \"+\" / / \"-\"", "sign = \"+\" / \"-\"")),
+            Some("RFC 5234 section 4"),
+        ),
+        UnexpectedTokenError => (
+            "The rule looks complete but is followed by more tokens, for example a line\n\
+            that continues a rule without being indented, or elements that are not\n\
+            separated by whitespace.",
+            Some(("a = \"b\" =/ \"c\"", "a = \"b\" / \"c\"")),
+            Some("RFC 5234 section 2.2"),
+        ),
+        MixedNumValError => (
+            "A numeric value is either a range of values with `-` or a concatenation of\n\
+            values with `.`, never both.",
+            Some(("a = %x31-39.41", "a = %x31-39 %x41")),
+            Some("RFC 5234 sections 2.3 and 3.4"),
+        ),
+        InvertedRangeError => (
+            "The lower bound of a value range is greater than the upper bound, so no\n\
+            value is in the range.",
+            Some(("octal = %x37-30", "octal = %x30-37")),
+            Some("RFC 5234 section 3.4"),
+        ),
+        UndefinedRuleError => (
+            "A rule is referenced but defined nowhere in the grammar and is not a core\n\
+            rule.",
+            Some(("a = b", "a = b\nb = \"b\"")),
+            Some("RFC 5234 section 2.1"),
+        ),
+        DuplicateRuleError => (
+            "A rule is defined twice with `=`. Use `=/` to add alternatives to a rule.",
+            Some(("a = \"a\"\na = \"b\"", "a = \"a\"\na =/ \"b\"")),
+            Some("RFC 5234 section 3.3"),
+        ),
+        IncrementalBeforeDefinitionError => (
+            "`=/` adds alternatives to a rule, the rule has to be defined with `=` first.",
+            Some(("a =/ \"b\"\na = \"a\"", "a = \"a\"\na =/ \"b\"")),
+            Some("RFC 5234 section 3.3"),
+        ),
+        UnreachableRuleWarning => (
+            "The first rule of a grammar is its start rule, a rule that is not\n\
+            referenced by it directly or indirectly is never used.",
+            Some(("a = \"a\"\nb = \"b\"", "a = \"a\" / b\nb = \"b\"")),
+            None,
+        ),
+        CoreRuleRedefinitionWarning => (
+            "A rule has the name of one of the core rules, and is used instead of it.\n\
+            Rename the rule or set `core_rules = false` in the `[grammar]` table of the\n\
+            config.",
+            Some(("DIGIT = %x30-37", "OCTDIGIT = %x30-37")),
+            Some("RFC 5234 appendix B"),
+        ),
+        ProseValWarning => (
+            "Prose describes an element in words, so it can't be tested or generated.\n\
+            Replace it with rules where possible.",
+            Some(("year = <four digits>", "year = 4DIGIT")),
+            Some("RFC 5234 section 4"),
+        ),
+        InconsistentCasingWarning => (
+            "Rule names are case-insensitive, so `Key`, `KEY` and `key` are the same rule,\n\
+            but the rule is spelled differently than at its first definition. Set\n\
+            `rule_case` in the `[grammar]` table of the config to \"lowercase\" or\n\
+            \"uppercase\" to prefer a casing for the rules the grammar defines.",
+            Some(("key = Value\nvalue = ALPHA", "key = value\nvalue = ALPHA")),
+            Some("RFC 5234 section 2.1"),
+        ),
+        BroadRangeWarning => (
+            "The range starts at %x00 and includes every ASCII control character, which\n\
+            is rarely what is meant. Narrow it down to the characters that are allowed.",
+            Some(("comment = \";\" *%x00-FF", "comment = \";\" *%x20-7E")),
+            None,
+        ),
+        RedundantGroupWarning => (
+            "The parentheses group a single element, or a sequence that is already\n\
+            grouped by where it is, so they can be left out.",
+            Some(("a = (\"a\") / (\"b\" \"c\")", "a = \"a\" / \"b\" \"c\"")),
+            Some("RFC 5234 section 3.5"),
+        ),
+        EmptyAlternativeWarning => (
+            "An alternative only matches the empty string, which makes the alternation\n\
+            optional in a roundabout way. Use an option instead.",
+            Some(("sign = \"+\" / \"-\" / \"\"", "sign = [\"+\" / \"-\"]")),
+            Some("RFC 5234 section 3.8"),
+        ),
+        LowercaseHexWarning => (
+            "Hexadecimal digits are case-insensitive, but RFC 5234 and the core rules\n\
+            write them in uppercase. `--fix` rewrites them.",
+            Some(("letter-j = %x6a", "letter-j = %x6A")),
+            Some("RFC 5234 section 2.3"),
+        ),
+        CoreRuleValueWarning => (
+            "The value matches exactly what one of the core rules matches, the name of\n\
+            the core rule says what is meant. `--fix` rewrites it.",
+            Some(("line = *VCHAR %x0D.0A", "line = *VCHAR CRLF")),
+            Some("RFC 5234 appendix B"),
+        ),
+        UnknownLintWarning => (
+            "An `allow` or `allow-file` comment names a lint that does not exist, so it\n\
+            allows nothing. Lints are named in kebab-case, as in the `[lint]` table of\n\
+            the config.",
+            Some((
+                "; abnf-toolkit: allow(unused-rules)",
+                "; abnf-toolkit: allow(unused-rule)",
+            )),
+            None,
+        ),
+        UnknownRuleError => (
+            "The rule given to `test` or `generate` is not defined in the grammar or the\n\
+            core rules.",
+            None,
+            None,
+        ),
+        InputRejectedError => (
+            "The input is not in the language of the rule. The report points at the\n\
+            furthest position the input could be matched to, along with what was\n\
+            expected there.",
+            None,
+            None,
+        ),
+        InputEncodingError => (
+            "The input could not be decoded in the grammar's encoding. Set `encoding` in\n\
+            the `[grammar]` table of the config to \"bytes\", \"utf-8\" or \"utf-16\".",
+            None,
+            Some("RFC 5234 section 2.4"),
+        ),
+        AmbiguousInputWarning => (
+            "The input matches the rule in more than one way, the report points at the\n\
+            innermost rule that has more than one derivation.",
+            Some(("a = *\" \" *\" \"", "a = *\" \"")),
+            None,
+        ),
+        UngeneratableRuleError => (
+            "Every derivation of the rule is infinite, for example because it always\n\
+            references itself, so no string can be generated for it.",
+            Some(("list = \"a\" list", "list = \"a\" [list]")),
+            None,
+        ),
+    };
+
+    Explanation {
+        description,
+        example,
+        section,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::check::Checker;
+    use crate::config::{LexerConfig, RuleCase};
+    use crate::core_rules::core_rules;
+    use crate::encoding::Encoding;
+    use crate::lexer::Lexer;
+    use crate::lint::Linter;
+    use crate::parser::Parser;
+    use std::collections::{HashMap, HashSet};
+    use strum::IntoEnumIterator;
+
+    // codes of the reports on a grammar, the way `check` reports them with
+    // the default config
+    fn codes(text: &str, extended: bool) -> Vec<&'static str> {
+        let config = LexerConfig { extended };
+        let reports = match Lexer::new(text, config, Encoding::Utf8).tokenize() {
+            Err(reports) => reports,
+            Ok((tokens, mut reports)) => match Parser::new(text, &tokens).parse() {
+                Err(err) => {
+                    reports.extend(err);
+                    reports
+                }
+                Ok(grammar) => {
+                    let core_rules = core_rules();
+                    reports.extend(Checker::new(text, &grammar, Some(&core_rules)).check());
+                    reports.extend(
+                        Linter::new(
+                            text,
+                            &grammar,
+                            Some(&core_rules),
+                            RuleCase::default(),
+                            HashMap::new(),
+                        )
+                        .lint(),
+                    );
+                    reports
+                }
+            },
+        };
+
+        reports
+            .iter()
+            .map(|report| report.get_kind().code())
+            .collect()
+    }
+
+    #[test]
+    fn examples() {
+        use ReportKind::*;
+
+        let mut wrong = Vec::new();
+        for kind in ReportKind::iter() {
+            let Some((bad, fixed)) = explain(&kind).example else {
+                continue;
+            };
+            let extended = matches!(
+                kind,
+                TerminalOverflowError | SurrogateTerminalError | TerminalRangeError
+            );
+
+            // saved as a file, which ends in a newline unless the example is
+            // about the end of the file
+            let bad_codes = match kind {
+                EofError => codes(bad, extended),
+                _ => codes(&format!("{bad}\n"), extended),
+            };
+            // only reported by `test` and `generate`
+            let reported = match kind {
+                AmbiguousInputWarning | UngeneratableRuleError => bad_codes.is_empty(),
+                _ => bad_codes.contains(&kind.code()),
+            };
+            if !reported {
+                wrong.push(format!(
+                    "{} {kind}: {bad:?} reports {bad_codes:?}",
+                    kind.code()
+                ));
+            }
+
+            let fixed_codes = codes(&format!("{fixed}\n"), extended);
+            if !fixed_codes.is_empty() {
+                wrong.push(format!(
+                    "{} {kind}: {fixed:?} reports {fixed_codes:?}",
+                    kind.code()
+                ));
+            }
+        }
+
+        assert!(wrong.is_empty(), "{}", wrong.join("\n"));
+    }
+
+    #[test]
+    fn codes_are_unique() {
+        let mut codes = HashSet::new();
+        for kind in ReportKind::iter() {
+            assert!(codes.insert(kind.code()), "{kind} reuses {}", kind.code());
+            assert_eq!(ReportKind::from_code(kind.code()), Some(kind));
+        }
+    }
+
+    #[test]
+    fn from_code() {
+        assert_eq!(
+            ReportKind::from_code("a0005"),
+            Some(ReportKind::UnclosedBracketError)
+        );
+        assert_eq!(ReportKind::from_code("A9999"), None);
+    }
+}
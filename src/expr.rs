@@ -0,0 +1,180 @@
+use crate::span::Span;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Expr {
+    pub kind: ExprKind,
+    pub span: Span,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ExprKind {
+    Alternation(Vec<Expr>),
+    Concatenation(Vec<Expr>),
+    Repetition {
+        min: usize,
+        max: Option<usize>,
+        expr: Box<Expr>,
+    },
+    Group(Box<Expr>),
+    Option(Box<Expr>),
+
+    // elements
+    Rule(String),
+    /// quoted strings are case-insensitive unless prefixed with `%s`
+    CharVal {
+        value: String,
+        case_sensitive: bool,
+    },
+    NumVal {
+        radix: Radix,
+        value: NumVal,
+    },
+    ProseVal(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Radix {
+    Binary,
+    Decimal,
+    Hexadecimal,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum NumVal {
+    /// `%x41-5A`
+    Range(u32, u32),
+    /// `%x0D.0A`, a single value is a sequence of one
+    Sequence(Vec<u32>),
+}
+
+impl Expr {
+    pub fn new(kind: ExprKind, span: Span) -> Self {
+        Expr { kind, span }
+    }
+
+    /// the expressions directly nested in this one
+    pub fn children(&self) -> &[Expr] {
+        match &self.kind {
+            ExprKind::Alternation(exprs) | ExprKind::Concatenation(exprs) => exprs,
+            ExprKind::Repetition { expr, .. } | ExprKind::Group(expr) | ExprKind::Option(expr) => {
+                std::slice::from_ref(expr)
+            }
+            ExprKind::Rule(_)
+            | ExprKind::CharVal { .. }
+            | ExprKind::NumVal { .. }
+            | ExprKind::ProseVal(_) => &[],
+        }
+    }
+
+    /// visit this expression and every expression nested in it, parents first
+    pub fn walk<'e>(&'e self, visit: &mut impl FnMut(&'e Expr)) {
+        visit(self);
+
+        match &self.kind {
+            ExprKind::Alternation(exprs) | ExprKind::Concatenation(exprs) => {
+                for expr in exprs {
+                    expr.walk(visit);
+                }
+            }
+            ExprKind::Repetition { expr, .. } | ExprKind::Group(expr) | ExprKind::Option(expr) => {
+                expr.walk(visit)
+            }
+            ExprKind::Rule(_)
+            | ExprKind::CharVal { .. }
+            | ExprKind::NumVal { .. }
+            | ExprKind::ProseVal(_) => {}
+        }
+    }
+}
+
+impl Radix {
+    pub fn base(&self) -> u32 {
+        match self {
+            Radix::Binary => 2,
+            Radix::Decimal => 10,
+            Radix::Hexadecimal => 16,
+        }
+    }
+
+    fn fmt_value(&self, f: &mut std::fmt::Formatter<'_>, value: u32) -> std::fmt::Result {
+        match self {
+            Radix::Binary => write!(f, "{value:07b}"),
+            Radix::Decimal => write!(f, "{value}"),
+            Radix::Hexadecimal => write!(f, "{value:02X}"),
+        }
+    }
+}
+
+impl std::fmt::Display for Radix {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Radix::Binary => write!(f, "b"),
+            Radix::Decimal => write!(f, "d"),
+            Radix::Hexadecimal => write!(f, "x"),
+        }
+    }
+}
+
+/// renders the expression back into ABNF
+impl std::fmt::Display for Expr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            ExprKind::Alternation(alternatives) => {
+                for (i, alternative) in alternatives.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " / ")?;
+                    }
+                    write!(f, "{alternative}")?;
+                }
+                Ok(())
+            }
+            ExprKind::Concatenation(items) => {
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " ")?;
+                    }
+                    write!(f, "{item}")?;
+                }
+                Ok(())
+            }
+            ExprKind::Repetition { min, max, expr } => {
+                match (min, max) {
+                    (min, Some(max)) if min == max => write!(f, "{min}")?,
+                    (0, None) => write!(f, "*")?,
+                    (0, Some(max)) => write!(f, "*{max}")?,
+                    (min, None) => write!(f, "{min}*")?,
+                    (min, Some(max)) => write!(f, "{min}*{max}")?,
+                }
+                write!(f, "{expr}")
+            }
+            ExprKind::Group(expr) => write!(f, "( {expr} )"),
+            ExprKind::Option(expr) => write!(f, "[ {expr} ]"),
+            ExprKind::Rule(name) => write!(f, "{name}"),
+            ExprKind::CharVal {
+                value,
+                case_sensitive: true,
+            } => write!(f, "%s\"{value}\""),
+            ExprKind::CharVal { value, .. } => write!(f, "\"{value}\""),
+            ExprKind::NumVal { radix, value } => {
+                write!(f, "%{radix}")?;
+                match value {
+                    NumVal::Range(min, max) => {
+                        radix.fmt_value(f, *min)?;
+                        write!(f, "-")?;
+                        radix.fmt_value(f, *max)
+                    }
+                    NumVal::Sequence(values) => {
+                        for (i, value) in values.iter().enumerate() {
+                            if i > 0 {
+                                write!(f, ".")?;
+                            }
+                            radix.fmt_value(f, *value)?;
+                        }
+                        Ok(())
+                    }
+                }
+            }
+            ExprKind::ProseVal(prose) => write!(f, "<{prose}>"),
+        }
+    }
+}
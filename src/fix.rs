@@ -0,0 +1,177 @@
+use crate::span::Span;
+
+// unchanged lines shown around each change of a diff
+const CONTEXT: usize = 3;
+
+/// a machine-applicable change to the source, replacing `span` with
+/// `replacement`, an insertion when the span is empty
+#[derive(Clone, Debug, PartialEq)]
+pub struct Edit {
+    pub span: Span,
+    pub replacement: String,
+}
+
+/// apply edits in source order, an edit that overlaps an earlier one is left
+/// out and the number of edits applied is returned along with the new source
+pub fn apply(src: &str, edits: &[Edit]) -> (String, usize) {
+    let mut edits = edits.iter().collect::<Vec<_>>();
+    edits.sort_by_key(|edit| (edit.span.start, edit.span.end));
+    edits.dedup();
+
+    let mut fixed = String::new();
+    let mut end = 0;
+    let mut applied = 0;
+    for edit in edits {
+        if edit.span.start < end {
+            continue;
+        }
+
+        fixed.push_str(&src[end..edit.span.start]);
+        fixed.push_str(&edit.replacement);
+        end = edit.span.end;
+        applied += 1;
+    }
+    fixed.push_str(&src[end..]);
+
+    (fixed, applied)
+}
+
+enum Line<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+/// unified diff of two versions of the file at `path`, empty when they are
+/// the same
+pub fn diff(path: &str, before: &str, after: &str) -> String {
+    let lines = diff_lines(
+        &before.lines().collect::<Vec<_>>(),
+        &after.lines().collect::<Vec<_>>(),
+    );
+
+    let changed = (0..lines.len())
+        .filter(|&i| !matches!(lines[i], Line::Same(_)))
+        .collect::<Vec<_>>();
+    if changed.is_empty() {
+        return String::new();
+    }
+
+    // changes closer than twice the context share a hunk
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for &i in &changed {
+        let start = i.saturating_sub(CONTEXT);
+        let end = (i + CONTEXT + 1).min(lines.len());
+        match hunks.last_mut() {
+            Some((_, last)) if start <= *last => *last = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+
+    let mut out = format!("--- {path}\n+++ {path}\n");
+    for (start, end) in hunks {
+        let before_start = 1 + lines[..start]
+            .iter()
+            .filter(|line| !matches!(line, Line::Added(_)))
+            .count();
+        let after_start = 1 + lines[..start]
+            .iter()
+            .filter(|line| !matches!(line, Line::Removed(_)))
+            .count();
+        let hunk = &lines[start..end];
+        let before_len = hunk
+            .iter()
+            .filter(|line| !matches!(line, Line::Added(_)))
+            .count();
+        let after_len = hunk
+            .iter()
+            .filter(|line| !matches!(line, Line::Removed(_)))
+            .count();
+
+        out.push_str(&format!(
+            "@@ -{before_start},{before_len} +{after_start},{after_len} @@\n"
+        ));
+        for line in hunk {
+            match line {
+                Line::Same(line) => out.push_str(&format!(" {line}\n")),
+                Line::Removed(line) => out.push_str(&format!("-{line}\n")),
+                Line::Added(line) => out.push_str(&format!("+{line}\n")),
+            }
+        }
+    }
+
+    out
+}
+
+// longest common subsequence of the lines, removals before additions
+fn diff_lines<'a>(before: &[&'a str], after: &[&'a str]) -> Vec<Line<'a>> {
+    // lcs[i][j] is the length of the longest common subsequence of
+    // before[i..] and after[j..]
+    let mut lcs = vec![vec![0; after.len() + 1]; before.len() + 1];
+    for i in (0..before.len()).rev() {
+        for j in (0..after.len()).rev() {
+            lcs[i][j] = match before[i] == after[j] {
+                true => lcs[i + 1][j + 1] + 1,
+                false => lcs[i + 1][j].max(lcs[i][j + 1]),
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut lines = Vec::new();
+    while i < before.len() || j < after.len() {
+        if i < before.len() && j < after.len() && before[i] == after[j] {
+            lines.push(Line::Same(before[i]));
+            i += 1;
+            j += 1;
+        } else if i < before.len() && (j == after.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            lines.push(Line::Removed(before[i]));
+            i += 1;
+        } else {
+            lines.push(Line::Added(after[j]));
+            j += 1;
+        }
+    }
+
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edit(start: usize, end: usize, replacement: &str) -> Edit {
+        Edit {
+            span: Span::new(start, end),
+            replacement: replacement.to_string(),
+        }
+    }
+
+    #[test]
+    fn applies_edits_in_order() {
+        let src = "a = (b) / %x0d";
+        let edits = [edit(10, 14, "%x0D"), edit(4, 7, "b")];
+
+        assert_eq!(apply(src, &edits), ("a = b / %x0D".to_string(), 2));
+    }
+
+    #[test]
+    fn skips_overlapping_edits() {
+        let src = "a = ((b c))";
+        let edits = [edit(4, 11, "(b c)"), edit(5, 10, "b c")];
+
+        assert_eq!(apply(src, &edits), ("a = (b c)".to_string(), 1));
+    }
+
+    #[test]
+    fn unified_diff() {
+        let before = "a = b\nb = (c)\nc = \"c\"\n";
+        let after = "a = b\nb = c\nc = \"c\"\n";
+
+        assert_eq!(
+            diff("g.abnf", before, after),
+            "--- g.abnf\n+++ g.abnf\n@@ -1,3 +1,3 @@\n a = b\n-b = (c)\n+b = c\n c = \"c\"\n"
+        );
+        assert_eq!(diff("g.abnf", before, before), "");
+    }
+}
@@ -0,0 +1,310 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::Serialize;
+
+use crate::earley::{Chart, EarleyGrammar, Item, Symbol};
+use crate::span::Span;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Label {
+    Rule(String),
+    /// a group, option or repetition
+    Anonymous,
+    Terminal,
+    /// the first symbols of a production, see `Forest`
+    Partial,
+}
+
+#[derive(Clone, Debug)]
+pub struct Node {
+    pub label: Label,
+    pub span: Span,
+    /// every way the node can be derived, more than one means ambiguity
+    pub families: Vec<Vec<usize>>,
+}
+
+/// a shared packed parse forest holding every derivation of an input
+///
+/// productions are binarised through `Partial` nodes so the forest stays
+/// polynomial in size even for highly ambiguous inputs
+#[derive(Clone, Debug)]
+pub struct Forest {
+    pub nodes: Vec<Node>,
+    pub root: usize,
+}
+
+/// a single derivation, only rules are kept, with their byte spans
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Tree {
+    pub rule: String,
+    pub start: usize,
+    pub end: usize,
+    pub children: Vec<Tree>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum Key {
+    Symbol(Symbol, usize, usize),
+    // production, dot, start, end
+    Partial(usize, usize, usize, usize),
+}
+
+enum Step {
+    Flatten(usize),
+    Rule(usize),
+}
+
+struct Builder<'a, 'g> {
+    grammar: &'a EarleyGrammar<'g>,
+    chart: &'a Chart,
+    input: &'a [u32],
+    offsets: &'a [usize],
+
+    nodes: Vec<Node>,
+    keys: HashMap<Key, usize>,
+    // nodes whose families are yet to be filled in
+    queue: Vec<(usize, Key)>,
+}
+
+impl Forest {
+    /// build the forest of an accepted input, `offsets` maps input positions
+    /// to byte offsets
+    pub fn new(
+        grammar: &EarleyGrammar,
+        chart: &Chart,
+        input: &[u32],
+        offsets: &[usize],
+        start: usize,
+    ) -> Self {
+        let mut builder = Builder {
+            grammar,
+            chart,
+            input,
+            offsets,
+            nodes: Vec::new(),
+            keys: HashMap::new(),
+            queue: Vec::new(),
+        };
+
+        let root = builder.node(Key::Symbol(Symbol::Nonterminal(start), 0, input.len()));
+
+        // a worklist rather than recursion, long inputs make deep derivations
+        while let Some((id, key)) = builder.queue.pop() {
+            builder.nodes[id].families = builder.families(key);
+        }
+
+        Forest {
+            nodes: builder.nodes,
+            root,
+        }
+    }
+
+    /// the innermost rule around the first ambiguity found, if any
+    pub fn ambiguity(&self) -> Option<&Node> {
+        let mut seen = HashSet::from([self.root]);
+        let mut stack = vec![(self.root, self.root)];
+
+        while let Some((id, rule)) = stack.pop() {
+            let node = &self.nodes[id];
+            let rule = match node.label {
+                Label::Rule(_) => id,
+                _ => rule,
+            };
+
+            if node.families.len() > 1 {
+                return Some(&self.nodes[rule]);
+            }
+
+            for &child in node.families.iter().flatten() {
+                if seen.insert(child) {
+                    stack.push((child, rule));
+                }
+            }
+        }
+
+        None
+    }
+
+    /// one derivation of the input, ambiguous nodes take the first
+    /// derivation found that does not loop back on itself
+    pub fn tree(&self) -> Tree {
+        let derivations = self.derivations();
+        self.tree_at(self.root, &derivations)
+    }
+
+    fn tree_at(&self, id: usize, derivations: &[Option<&[usize]>]) -> Tree {
+        let node = &self.nodes[id];
+        let Label::Rule(rule) = &node.label else {
+            unreachable!("trees are only built for rules");
+        };
+
+        // everything between this rule and the rules it uses is flattened
+        let mut children = Vec::new();
+        let mut stack = vec![Step::Flatten(id)];
+        while let Some(next) = stack.pop() {
+            match next {
+                Step::Flatten(id) => {
+                    let family = derivations[id].expect("accepted inputs have a derivation");
+                    for &child in family.iter().rev() {
+                        match self.nodes[child].label {
+                            Label::Rule(_) => stack.push(Step::Rule(child)),
+                            Label::Terminal => {}
+                            Label::Anonymous | Label::Partial => stack.push(Step::Flatten(child)),
+                        }
+                    }
+                }
+                Step::Rule(rule) => children.push(self.tree_at(rule, derivations)),
+            }
+        }
+
+        Tree {
+            rule: rule.clone(),
+            start: node.span.start,
+            end: node.span.end,
+            children,
+        }
+    }
+
+    // a family of every node that bottoms out, picked once all its children
+    // have one so following them never loops, children mostly come after
+    // their parents so a backwards pass settles nearly everything
+    fn derivations(&self) -> Vec<Option<&[usize]>> {
+        let mut derivations = self
+            .nodes
+            .iter()
+            .map(|node| (node.label == Label::Terminal).then_some(&[][..]))
+            .collect::<Vec<Option<&[usize]>>>();
+
+        let mut changed = true;
+        while changed {
+            changed = false;
+
+            for (id, node) in self.nodes.iter().enumerate().rev() {
+                if derivations[id].is_some() {
+                    continue;
+                }
+
+                let family = node
+                    .families
+                    .iter()
+                    .find(|family| family.iter().all(|&child| derivations[child].is_some()));
+                if let Some(family) = family {
+                    derivations[id] = Some(family);
+                    changed = true;
+                }
+            }
+        }
+
+        derivations
+    }
+}
+
+impl std::fmt::Display for Tree {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut stack = vec![(self, 0)];
+        while let Some((tree, depth)) = stack.pop() {
+            writeln!(
+                f,
+                "{:indent$}{} {}..{}",
+                "",
+                tree.rule,
+                tree.start,
+                tree.end,
+                indent = depth * 2
+            )?;
+            stack.extend(tree.children.iter().rev().map(|child| (child, depth + 1)));
+        }
+        Ok(())
+    }
+}
+
+impl Builder<'_, '_> {
+    fn node(&mut self, key: Key) -> usize {
+        if let Some(&id) = self.keys.get(&key) {
+            return id;
+        }
+
+        let (label, start, end) = match key {
+            Key::Symbol(Symbol::Nonterminal(nonterminal), start, end) => {
+                let label = match self.grammar.nonterminals[nonterminal].name {
+                    Some(name) => Label::Rule(name.to_string()),
+                    None => Label::Anonymous,
+                };
+                (label, start, end)
+            }
+            Key::Symbol(Symbol::Terminal(_), start, end) => (Label::Terminal, start, end),
+            Key::Partial(_, _, start, end) => (Label::Partial, start, end),
+        };
+
+        let id = self.nodes.len();
+        self.nodes.push(Node {
+            label,
+            span: Span::new(self.offsets[start], self.offsets[end]),
+            families: Vec::new(),
+        });
+        self.keys.insert(key, id);
+        self.queue.push((id, key));
+
+        id
+    }
+
+    fn families(&mut self, key: Key) -> Vec<Vec<usize>> {
+        let mut families = Vec::new();
+
+        match key {
+            Key::Symbol(Symbol::Terminal(_), ..) => {}
+            Key::Symbol(Symbol::Nonterminal(nonterminal), start, end) => {
+                for &production in &self.grammar.nonterminals[nonterminal].productions {
+                    let len = self.grammar.productions[production].rhs.len();
+                    let item = Item {
+                        production,
+                        dot: len,
+                        origin: start,
+                    };
+                    if !self.chart.contains(end, item) {
+                        continue;
+                    }
+
+                    if len == 0 {
+                        families.push(Vec::new());
+                    } else {
+                        families.push(vec![self.node(Key::Partial(production, len, start, end))]);
+                    }
+                }
+            }
+            Key::Partial(production, dot, start, end) => {
+                let symbol = self.grammar.productions[production].rhs[dot - 1];
+
+                if dot == 1 {
+                    if self.derives(symbol, start, end) {
+                        families.push(vec![self.node(Key::Symbol(symbol, start, end))]);
+                    }
+                    return families;
+                }
+
+                // every split point where the shorter prefix ends and the
+                // last symbol takes over
+                for split in start..=end {
+                    let prefix = Item {
+                        production,
+                        dot: dot - 1,
+                        origin: start,
+                    };
+                    if self.chart.contains(split, prefix) && self.derives(symbol, split, end) {
+                        families.push(vec![
+                            self.node(Key::Partial(production, dot - 1, start, split)),
+                            self.node(Key::Symbol(symbol, split, end)),
+                        ]);
+                    }
+                }
+            }
+        }
+
+        families
+    }
+
+    fn derives(&self, symbol: Symbol, start: usize, end: usize) -> bool {
+        self.grammar
+            .derives(self.chart, self.input, symbol, start, end)
+    }
+}
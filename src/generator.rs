@@ -0,0 +1,393 @@
+use std::collections::HashMap;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::encoding::Encoding;
+use crate::expr::{Expr, ExprKind, NumVal};
+use crate::grammar::{Grammar, Name};
+use crate::report::Report;
+use crate::report_kind::ReportKind;
+
+// cost of an expression that can never be expanded, e.g. prose
+const IMPOSSIBLE: usize = usize::MAX;
+
+// extra repetitions tried on top of the minimum of an unbounded repetition
+const EXTRA_REPETITIONS: usize = 8;
+
+pub struct GeneratorConfig {
+    pub seed: u64,
+    /// rule nesting after which the shortest way to finish is taken
+    pub max_depth: usize,
+    /// output length after which the shortest way to finish is taken
+    pub max_length: usize,
+    /// relative weight of an alternative that is a single rule reference,
+    /// every other alternative weighs 1
+    pub weights: HashMap<String, u32>,
+    /// how generated values are written out
+    pub encoding: Encoding,
+}
+
+/// walks a grammar from a rule, producing random strings in its language
+pub struct Generator<'g> {
+    rules: HashMap<Name<'g>, Vec<&'g Expr>>,
+    // rule nesting needed to fully expand each rule
+    costs: HashMap<Name<'g>, usize>,
+
+    config: GeneratorConfig,
+    rng: StdRng,
+}
+
+impl<'g> Generator<'g> {
+    pub fn new(
+        grammar: &'g Grammar,
+        core_rules: Option<&'g Grammar>,
+        config: GeneratorConfig,
+    ) -> Self {
+        let rules = grammar.expressions(core_rules);
+
+        let mut generator = Generator {
+            costs: rules.keys().map(|&name| (name, IMPOSSIBLE)).collect(),
+            rules,
+            rng: StdRng::seed_from_u64(config.seed),
+            config,
+        };
+        generator.compute_costs();
+
+        generator
+    }
+
+    pub fn generate(&mut self, rule: &str) -> Result<Vec<u8>, Report> {
+        let Some(&name) = self.rules.keys().find(|&&name| name == Name(rule)) else {
+            return Err(
+                Report::new(ReportKind::UnknownRuleError, None, String::new())
+                    .note(format!("no rule named `{rule}`")),
+            );
+        };
+
+        if self.costs[&name] == IMPOSSIBLE {
+            return Err(
+                Report::new(ReportKind::UngeneratableRuleError, None, String::new())
+                    .note(format!("`{rule}` depends on prose or undefined rules")),
+            );
+        }
+
+        let mut out = Vec::new();
+        self.generate_rule(name, 0, &mut out);
+
+        Ok(self.config.encoding.encode(&out))
+    }
+
+    // iterate until no rule gets cheaper, rules that never settle are impossible
+    fn compute_costs(&mut self) {
+        let mut changed = true;
+        while changed {
+            changed = false;
+
+            for (&name, exprs) in &self.rules {
+                let cost = exprs
+                    .iter()
+                    .map(|expr| self.cost(expr))
+                    .min()
+                    .unwrap_or(IMPOSSIBLE)
+                    .saturating_add(1);
+
+                if cost < self.costs[&name] {
+                    self.costs.insert(name, cost);
+                    changed = true;
+                }
+            }
+        }
+    }
+
+    fn cost(&self, expr: &Expr) -> usize {
+        match &expr.kind {
+            ExprKind::Alternation(alternatives) => alternatives
+                .iter()
+                .map(|alternative| self.cost(alternative))
+                .min()
+                .unwrap_or(IMPOSSIBLE),
+            ExprKind::Concatenation(items) => {
+                items.iter().map(|item| self.cost(item)).max().unwrap_or(0)
+            }
+            ExprKind::Repetition { min: 0, .. } | ExprKind::Option(_) => 0,
+            ExprKind::Repetition { expr, .. } | ExprKind::Group(expr) => self.cost(expr),
+            ExprKind::Rule(name) => self.costs.get(&Name(name)).copied().unwrap_or(IMPOSSIBLE),
+            ExprKind::CharVal { .. } | ExprKind::NumVal { .. } => 0,
+            ExprKind::ProseVal(_) => IMPOSSIBLE,
+        }
+    }
+
+    fn generate_rule(&mut self, name: Name<'g>, depth: usize, out: &mut Vec<u32>) {
+        let exprs = self.rules[&name].clone();
+        let expr = if self.out_of_budget(depth, out) {
+            self.cheapest(&exprs)
+        } else {
+            self.pick(&exprs)
+        };
+
+        self.generate_expr(expr, depth + 1, out);
+    }
+
+    fn generate_expr(&mut self, expr: &'g Expr, depth: usize, out: &mut Vec<u32>) {
+        match &expr.kind {
+            ExprKind::Alternation(alternatives) => {
+                let alternatives = alternatives.iter().collect::<Vec<&Expr>>();
+                let alternative = if self.out_of_budget(depth, out) {
+                    self.cheapest(&alternatives)
+                } else {
+                    self.pick(&alternatives)
+                };
+
+                self.generate_expr(alternative, depth, out);
+            }
+            ExprKind::Concatenation(items) => {
+                for item in items {
+                    self.generate_expr(item, depth, out);
+                }
+            }
+            ExprKind::Repetition { min, max, expr } => {
+                let upper = max.unwrap_or(min + EXTRA_REPETITIONS);
+                let count = if self.cost(expr) == IMPOSSIBLE {
+                    0
+                } else {
+                    self.rng.gen_range(*min..=upper.max(*min))
+                };
+
+                for i in 0..count {
+                    if i >= *min && self.out_of_budget(depth, out) {
+                        break;
+                    }
+                    self.generate_expr(expr, depth, out);
+                }
+            }
+            ExprKind::Group(expr) => self.generate_expr(expr, depth, out),
+            ExprKind::Option(expr) => {
+                if self.cost(expr) != IMPOSSIBLE
+                    && !self.out_of_budget(depth, out)
+                    && self.rng.gen_bool(0.5)
+                {
+                    self.generate_expr(expr, depth, out);
+                }
+            }
+            ExprKind::Rule(name) => {
+                let (&name, _) = self
+                    .rules
+                    .get_key_value(&Name(name))
+                    .expect("only rules with a finite cost are generated");
+                self.generate_rule(name, depth, out);
+            }
+            // any casing is valid for a case-insensitive char-val
+            ExprKind::CharVal {
+                value,
+                case_sensitive,
+            } => {
+                for c in value.chars() {
+                    let c = match *case_sensitive || self.rng.gen_bool(0.5) {
+                        true => c,
+                        false if c.is_ascii_lowercase() => c.to_ascii_uppercase(),
+                        false => c.to_ascii_lowercase(),
+                    };
+                    out.extend(self.config.encoding.encode_char(c));
+                }
+            }
+            ExprKind::NumVal { value, .. } => match value {
+                NumVal::Range(min, max) => out.push(self.pick_value(*min, *max)),
+                NumVal::Sequence(values) => out.extend(values),
+            },
+            ExprKind::ProseVal(_) => unreachable!("prose is never picked"),
+        }
+    }
+
+    /// weighted random choice among the expressions that can be expanded
+    fn pick(&mut self, exprs: &[&'g Expr]) -> &'g Expr {
+        let weights = exprs
+            .iter()
+            .map(|expr| match &expr.kind {
+                _ if self.cost(expr) == IMPOSSIBLE => 0,
+                ExprKind::Rule(name) => self
+                    .config
+                    .weights
+                    .iter()
+                    .find(|(rule, _)| Name(rule) == Name(name))
+                    .map_or(1, |(_, &weight)| weight),
+                _ => 1,
+            })
+            .collect::<Vec<u32>>();
+
+        let total = weights.iter().map(|&w| w as u64).sum::<u64>();
+        if total == 0 {
+            return self.cheapest(exprs);
+        }
+
+        let mut choice = self.rng.gen_range(0..total);
+        for (expr, weight) in exprs.iter().zip(weights) {
+            if choice < weight as u64 {
+                return expr;
+            }
+            choice -= weight as u64;
+        }
+
+        unreachable!()
+    }
+
+    fn cheapest(&self, exprs: &[&'g Expr]) -> &'g Expr {
+        exprs
+            .iter()
+            .min_by_key(|expr| self.cost(expr))
+            .expect("rules and alternations are never empty")
+    }
+
+    fn pick_value(&mut self, min: u32, max: u32) -> u32 {
+        if self.config.encoding != Encoding::Utf8 || max < 0xD800 || min > 0xDFFF {
+            return self.rng.gen_range(min..=max);
+        }
+
+        // surrogates are not chars in UTF-8, draw from the range without them
+        // and shift the values past them. the lexer rejects surrogate bounds,
+        // so some value is always left
+        let surrogates = max.min(0xDFFF) + 1 - min.max(0xD800);
+        if surrogates > max - min {
+            return min;
+        }
+        match self.rng.gen_range(min..=max - surrogates) {
+            value if value >= 0xD800 => value + surrogates,
+            value => value,
+        }
+    }
+
+    fn out_of_budget(&self, depth: usize, out: &[u32]) -> bool {
+        depth >= self.config.max_depth || out.len() >= self.config.max_length
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::LexerConfig;
+    use crate::core_rules::core_rules;
+    use crate::lexer::Lexer;
+    use crate::matcher::Matcher;
+    use crate::parser::Parser;
+
+    fn parse(text: &str) -> Grammar {
+        let (tokens, _) = Lexer::new(text, LexerConfig { extended: true }, Encoding::default())
+            .tokenize()
+            .expect("test grammar should lex");
+
+        Parser::new(text, &tokens)
+            .parse()
+            .expect("test grammar should parse")
+    }
+
+    fn config(seed: u64) -> GeneratorConfig {
+        GeneratorConfig {
+            seed,
+            max_depth: 16,
+            max_length: 64,
+            weights: HashMap::new(),
+            encoding: Encoding::Utf8,
+        }
+    }
+
+    /// every generated string has to be accepted by the matcher
+    fn generates_valid(text: &str, rule: &str) {
+        let grammar = parse(text);
+        let core_rules = core_rules();
+
+        for seed in 0..32 {
+            let out = Generator::new(&grammar, Some(&core_rules), config(seed))
+                .generate(rule)
+                .unwrap();
+
+            let matched = Matcher::new(&grammar, Some(&core_rules), &out, Encoding::Utf8)
+                .and_then(|matcher| matcher.matches(rule).map(|_| ()));
+            if let Err(err) = matched {
+                let out = String::from_utf8_lossy(&out);
+                panic!("generated {out:?} for seed {seed} is rejected\n{err}");
+            }
+        }
+    }
+
+    #[test]
+    fn simple_grammar() {
+        generates_valid(
+            "a = 1*3(\"ab\" / %x30-39) [\"?\"] *b\nb = %x41.42 / HEXDIG",
+            "a",
+        );
+    }
+
+    #[test]
+    fn case_sensitive_strings() {
+        generates_valid("a = 1*(%s\"aB\" / %i\"cD\")", "a");
+
+        let grammar = parse("a = %s\"aB\"");
+        let out = Generator::new(&grammar, None, config(0)).generate("a");
+        assert_eq!(out.unwrap(), b"aB");
+    }
+
+    #[test]
+    fn skips_surrogates() {
+        generates_valid("a = 1*%xD7FF-E000", "a");
+    }
+
+    #[test]
+    fn recursion_is_bounded() {
+        generates_valid("a = \"(\" a \")\" / \"(\" *a \")\" / \"x\"", "a");
+    }
+
+    #[test]
+    fn cln() {
+        generates_valid(include_str!("../cln.abnf"), "cln");
+    }
+
+    #[test]
+    fn encodings() {
+        let grammar = parse("a = 1*%x80-FF \"\u{e9}\"");
+
+        for encoding in [Encoding::Bytes, Encoding::Utf8, Encoding::Utf16] {
+            let mut config = config(0);
+            config.encoding = encoding;
+
+            let out = Generator::new(&grammar, None, config)
+                .generate("a")
+                .unwrap();
+            let matched = Matcher::new(&grammar, None, &out, encoding)
+                .and_then(|matcher| matcher.matches("a").map(|_| ()));
+            assert!(matched.is_ok(), "{encoding:?} output {out:?} is rejected");
+        }
+    }
+
+    #[test]
+    fn same_seed_same_output() {
+        let grammar = parse("a = *(\"x\" / \"y\" / \"z\")");
+
+        let first = Generator::new(&grammar, None, config(7)).generate("a");
+        let second = Generator::new(&grammar, None, config(7)).generate("a");
+        assert_eq!(first.unwrap(), second.unwrap());
+    }
+
+    #[test]
+    fn weights() {
+        let grammar = parse("a = b / c\nb = \"b\"\nc = \"c\"");
+        let mut config = config(0);
+        config.weights.insert("c".to_string(), 0);
+
+        let mut generator = Generator::new(&grammar, None, config);
+        for _ in 0..16 {
+            assert_eq!(generator.generate("a").unwrap().to_ascii_lowercase(), b"b");
+        }
+    }
+
+    #[test]
+    fn prose_is_avoided() {
+        let grammar = parse("a = <prose> / \"x\"\nb = <prose>");
+        let mut generator = Generator::new(&grammar, None, config(0));
+
+        assert_eq!(generator.generate("a").unwrap().to_ascii_lowercase(), b"x");
+        assert_eq!(
+            generator.generate("b").unwrap_err().get_kind(),
+            ReportKind::UngeneratableRuleError
+        );
+    }
+}
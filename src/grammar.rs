@@ -0,0 +1,117 @@
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+use crate::expr::Expr;
+use crate::span::Span;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Grammar {
+    pub rules: Vec<Rule>,
+    /// every comment, from the `;` up to the end of its line
+    pub comments: Vec<Span>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rule {
+    pub name: String,
+    pub defined_as: DefinedAs,
+    pub expr: Expr,
+
+    pub name_span: Span,
+    pub span: Span,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum DefinedAs {
+    /// `=`
+    Basic,
+    /// `=/`
+    Incremental,
+}
+
+/// a rulename as a key, rulenames are case-insensitive so `Key`, `KEY` and
+/// `key` are the same rule
+#[derive(Clone, Copy, Debug, Eq)]
+pub struct Name<'g>(pub &'g str);
+
+impl PartialEq for Name<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.eq_ignore_ascii_case(other.0)
+    }
+}
+
+impl Hash for Name<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for b in self.0.bytes() {
+            state.write_u8(b.to_ascii_lowercase());
+        }
+        state.write_u8(0xff);
+    }
+}
+
+impl std::fmt::Display for Name<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Grammar {
+    /// every definition of each rule, in source order
+    pub fn definitions(&self) -> HashMap<Name<'_>, Vec<&Rule>> {
+        let mut definitions: HashMap<Name, Vec<&Rule>> = HashMap::new();
+        for rule in &self.rules {
+            definitions.entry(Name(&rule.name)).or_default().push(rule);
+        }
+        definitions
+    }
+
+    /// the expressions of every rule, with core rules for the names this
+    /// grammar does not define itself
+    pub fn expressions<'g>(
+        &'g self,
+        core_rules: Option<&'g Grammar>,
+    ) -> HashMap<Name<'g>, Vec<&'g Expr>> {
+        let mut expressions: HashMap<Name, Vec<&Expr>> = HashMap::new();
+        for rule in &self.rules {
+            expressions
+                .entry(Name(&rule.name))
+                .or_default()
+                .push(&rule.expr);
+        }
+
+        for rule in core_rules.into_iter().flat_map(|core| &core.rules) {
+            if !self.rules.iter().any(|r| Name(&r.name) == Name(&rule.name)) {
+                expressions
+                    .entry(Name(&rule.name))
+                    .or_default()
+                    .push(&rule.expr);
+            }
+        }
+
+        expressions
+    }
+}
+
+impl std::fmt::Display for DefinedAs {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DefinedAs::Basic => write!(f, "="),
+            DefinedAs::Incremental => write!(f, "=/"),
+        }
+    }
+}
+
+impl std::fmt::Display for Rule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {} {}", self.name, self.defined_as, self.expr)
+    }
+}
+
+impl std::fmt::Display for Grammar {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for rule in &self.rules {
+            writeln!(f, "{rule}")?;
+        }
+        Ok(())
+    }
+}
@@ -1,7 +1,9 @@
 use crate::config::LexerConfig;
+use crate::encoding::Encoding;
 use crate::position::Position;
 use crate::report::Report;
 use crate::report_kind::ReportKind;
+use crate::span::Span;
 use crate::token::Token;
 use crate::token_kind::TokenKind;
 
@@ -18,13 +20,21 @@ pub struct Lexer<'s> {
 
     current_line: &'s str,
 
-    open_brackets: Vec<TokenKind>,
+    // kind and position of every bracket that is not closed yet
+    open_brackets: Vec<(TokenKind, Position)>,
+
+    // every report, including those that don't stop the current token from
+    // being lexed
+    reports: Vec<Report>,
+    // set once part of the source is left out of the tokens
+    failed: bool,
 
     config: LexerConfig,
+    encoding: Encoding,
 }
 
 impl<'s> Lexer<'s> {
-    pub fn new(source: &'s str, config: LexerConfig) -> Self {
+    pub fn new(source: &'s str, config: LexerConfig, encoding: Encoding) -> Self {
         let mut chars = source.chars();
         let next = chars.next();
 
@@ -41,16 +51,24 @@ impl<'s> Lexer<'s> {
             token_end: Position::new(),
             tokens: Vec::new(),
             open_brackets: Vec::new(),
+            reports: Vec::new(),
+            failed: false,
             current_line: &source[..index],
             chars,
             next,
             config,
+            encoding,
         }
     }
 
-    pub fn tokenize(&mut self) -> Result<Vec<Token<'s>>, Vec<Report>> {
-        let mut reports: Vec<Report> = Vec::new();
-
+    /// tokens are lossless, concatenating their lexemes reproduces the source.
+    /// they are returned along with the reports that didn't stop a token from
+    /// being lexed, such as a value outside of US-ASCII, which may still be
+    /// errors
+    ///
+    /// lexing goes on after an error so every error in the source is reported
+    /// at once, see `recover` for where it picks up again
+    pub fn tokenize(&mut self) -> Result<(Vec<Token<'s>>, Vec<Report>), Vec<Report>> {
         while !self.is_at_end() {
             let start = match self.next {
                 Some(start) => start,
@@ -58,26 +76,38 @@ impl<'s> Lexer<'s> {
             };
             // append errors to error vector, ignore Ok value
             if let Err(report) = self.lex(start) {
-                reports.push(report);
+                self.reports.push(report);
+                self.recover();
+                self.failed = true;
             }
         }
 
         self.add_token(TokenKind::EOF);
 
-        // check for unclosed brackets
-        if !self.open_brackets.is_empty() {
-            reports.push(Report::new(
+        // check for unclosed brackets, pointing at the outermost one
+        if let Some((_, open)) = self.open_brackets.first() {
+            let (end, closing) = self.missing_brackets(open.offset);
+            let mut report = Report::from_span(
                 ReportKind::UnclosedBracketError,
-                None,
-                self.current_line.into(),
-            ))
+                self.src,
+                Span::new(open.offset, open.offset + 1),
+            )
+            .help(format!("close it with `{closing}` at the end of the rule"));
+            // where the rule ends is only known when no source was dropped
+            if !self.failed {
+                report = report.fix(Span::new(end, end), closing);
+            }
+
+            self.reports.push(report);
+            self.failed = true;
         }
 
-        if !reports.is_empty() {
+        let reports = std::mem::take(&mut self.reports);
+        if self.failed {
             return Err(reports);
         }
 
-        Ok(self.tokens.clone())
+        Ok((self.tokens.clone(), reports))
     }
 
     fn lex(&mut self, start: char) -> LexResult<()> {
@@ -86,12 +116,10 @@ impl<'s> Lexer<'s> {
             ')' => self.lex_bracket(TokenKind::RightParen)?,
             '[' => self.lex_bracket(TokenKind::LeftSquare)?,
             ']' => self.lex_bracket(TokenKind::RightSquare)?,
-            '<' => self.lex_bracket(TokenKind::LeftAngle)?,
-            '>' => self.lex_bracket(TokenKind::RightAngle)?,
 
             '.' => self.lex_single(TokenKind::Dot)?,
             '-' => self.lex_single(TokenKind::Range)?,
-            '*' => self.lex_single(TokenKind::Star)?,
+            '*' => self.lex_repeat()?,
             '/' => self.lex_single(TokenKind::Slash)?,
 
             '=' => self.lex_assignment()?,
@@ -102,40 +130,65 @@ impl<'s> Lexer<'s> {
             '\n' | '\r' => self.lex_eol()?,
 
             '"' => self.lex_string_literal()?,
+            '<' => self.lex_prose()?,
 
-            _ if start.is_ascii_digit() => self.lex_number_literal(TokenKind::Number)?,
+            _ if start.is_ascii_digit() => self.lex_repeat()?,
             _ if start.is_ascii_alphabetic() => self.lex_identifier()?,
+            // a lone character can't break the tokens around it, so it is
+            // reported and skipped without recovering. the tokens are no
+            // longer lossless though, so lexing still fails
             _ => {
-                self.advance()?; // continue lexing
-
-                if self.is_at_end() {
-                    return Err(Report::new(
-                        ReportKind::UnableToParseError,
-                        Some(self.token_end.clone()),
-                        self.current_line.into(),
-                    ));
-                }
+                let pos = self.token_end.clone();
+                self.advance()?;
+                self.reports.push(
+                    self.report_at(ReportKind::UnexpectedCharacterError, pos)
+                        .note(format!("found {:?}, U+{:04X}", start, start as u32)),
+                );
+                self.token_start = self.token_end.clone();
+                self.failed = true;
             }
         };
 
         Ok(())
     }
 
+    // after an error the cursor may be in the middle of a token, e.g. at the
+    // `G` of `%x4G`, lexing from there would report the rest of the token as
+    // errors of its own. skip to the next character that always separates
+    // tokens instead and drop what was skipped. brackets, slashes and comments
+    // are still lexed, or a dropped `)` would be reported as unclosed
+    fn recover(&mut self) {
+        while self.next.is_some_and(|c| {
+            !matches!(
+                c,
+                ' ' | '\t' | '\n' | '\r' | '(' | ')' | '[' | ']' | '/' | ';'
+            )
+        }) {
+            // can't fail, there is a next char
+            let _ = self.advance();
+        }
+
+        self.token_start = self.token_end.clone();
+    }
+
+    // char-val = DQUOTE *(%x20-21 / %x23-7E) DQUOTE, on a single line
     fn lex_string_literal(&mut self) -> LexResult<()> {
         self.advance()?; // get first "
-        while let Some(peeked) = self.next {
-            if peeked != '"' && !self.is_at_end() {
-                self.advance()?;
-            } else {
+        while let Some(c) = self.next {
+            if matches!(c, '"' | '\n' | '\r') {
                 break;
             }
+
+            if !matches!(c, '\x20'..='\x21' | '\x23'..='\x7E') {
+                self.report_character(c, ReportKind::StringCharacterError);
+            }
+            self.advance()?;
         }
 
-        if self.is_at_end() {
-            return Err(Report::new(
+        if !self.next_is('"') {
+            return Err(self.report_at(
                 ReportKind::UnterminatedStringError,
-                Some(self.token_start.clone()),
-                self.current_line.into(),
+                self.token_start.clone(),
             ));
         }
 
@@ -144,6 +197,47 @@ impl<'s> Lexer<'s> {
         Ok(())
     }
 
+    // prose-val = "<" *(%x20-3D / %x3F-7E) ">", free form text so one token
+    fn lex_prose(&mut self) -> LexResult<()> {
+        self.advance()?; // get <
+
+        while let Some(c) = self.next {
+            if matches!(c, '>' | '\n' | '\r') {
+                break;
+            }
+
+            if !matches!(c, '\x20'..='\x3D' | '\x3F'..='\x7E') {
+                self.report_character(c, ReportKind::ProseCharacterError);
+            }
+            self.advance()?;
+        }
+
+        if !self.next_is('>') {
+            return Err(
+                self.report_at(ReportKind::UnterminatedProseError, self.token_start.clone())
+            );
+        }
+
+        self.advance()?; // grab >
+        self.add_token(TokenKind::Prose);
+
+        Ok(())
+    }
+
+    // report a character not allowed in a literal, extended mode allows
+    // any non-ASCII character
+    fn report_character(&mut self, c: char, kind: ReportKind) {
+        if self.config.extended && !c.is_ascii() {
+            return;
+        }
+
+        let offset = self.token_end.offset;
+        self.reports.push(
+            Report::from_span(kind, self.src, Span::new(offset, offset + c.len_utf8()))
+                .note(format!("found {:?}, U+{:04X}", c, c as u32)),
+        );
+    }
+
     fn lex_number_literal(&mut self, kind: TokenKind) -> LexResult<()> {
         while let Some(peeked) = self.next {
             if peeked.is_ascii_digit() && !self.is_at_end() {
@@ -157,6 +251,55 @@ impl<'s> Lexer<'s> {
         Ok(())
     }
 
+    fn lex_repeat(&mut self) -> LexResult<()> {
+        let start = self.token_start.clone();
+
+        let min = self.lex_digits()?;
+        let max = if self.advance_if_next_is('*')? {
+            self.lex_digits()?
+        } else {
+            min
+        };
+        let min = min.unwrap_or(0);
+
+        self.add_token(TokenKind::Repeat { min, max });
+
+        if max.is_some_and(|max| min > max) {
+            return Err(self.report_at(ReportKind::InvertedRepeatError, start));
+        }
+
+        // `3 * 5` would otherwise lex as three repeats in a row. take the rest
+        // of the broken prefix, `WSP "*" WSP DIGIT`, so it is reported once
+        if self.next_is_whitespace() {
+            self.skip_whitespace()?;
+            if self.advance_if_next_is('*')? {
+                self.skip_whitespace()?;
+            }
+            self.lex_digits()?;
+
+            return Err(self.report_at(ReportKind::RepeatWhitespaceError, start));
+        }
+
+        Ok(())
+    }
+
+    fn lex_digits(&mut self) -> LexResult<Option<usize>> {
+        let start = self.token_end.clone();
+        while self.next.is_some_and(|c| c.is_ascii_digit()) {
+            self.advance()?;
+        }
+
+        let digits = &self.src[start.offset..self.token_end.offset];
+        if digits.is_empty() {
+            return Ok(None);
+        }
+
+        digits
+            .parse::<usize>()
+            .map(Some)
+            .map_err(|_| self.report_at(ReportKind::NaNError, start))
+    }
+
     fn lex_identifier(&mut self) -> LexResult<()> {
         self.advance()?;
         while let Some(c) = self.next {
@@ -188,9 +331,12 @@ impl<'s> Lexer<'s> {
                 ))
             }
             Some(terminal) => match terminal {
-                'b' => TokenKind::TerminalBinary,
-                'd' => TokenKind::TerminalDecimal,
-                'x' => TokenKind::TerminalHexadecimal,
+                // literal text is case-insensitive, so is the base
+                'b' | 'B' => TokenKind::TerminalBinary,
+                'd' | 'D' => TokenKind::TerminalDecimal,
+                'x' | 'X' => TokenKind::TerminalHexadecimal,
+                's' | 'S' => TokenKind::TerminalSensitive,
+                'i' | 'I' => TokenKind::TerminalInsensitive,
                 ' ' | '\t' => {
                     return Err(Report::new(
                         ReportKind::NoTerminalFoundError,
@@ -214,6 +360,16 @@ impl<'s> Lexer<'s> {
             TokenKind::TerminalBinary => self.lex_terminal_binary()?,
             TokenKind::TerminalDecimal => self.lex_terminal_decimal()?,
             TokenKind::TerminalHexadecimal => self.lex_terminal_hexadecimal()?,
+            TokenKind::TerminalSensitive | TokenKind::TerminalInsensitive => {
+                if !self.next_is('"') {
+                    return Err(Report::new(
+                        ReportKind::ExpectedStringError,
+                        Some(self.token_end.clone()),
+                        self.current_line.into(),
+                    ));
+                }
+                self.lex_string_literal()?
+            }
             _ => unreachable!(),
         };
 
@@ -221,134 +377,116 @@ impl<'s> Lexer<'s> {
     }
 
     fn lex_terminal_binary(&mut self) -> LexResult<()> {
-        self.lex_number_literal(TokenKind::Binary)?;
+        loop {
+            let start = self.token_end.clone();
+            self.lex_number_literal(TokenKind::Binary)?;
+            let digits = &self.src[start.offset..self.token_end.offset];
 
-        if self.next_is('-') || self.next_is('.') {
-            self.lex_single(TokenKind::Range)?;
-            self.lex_terminal_binary()?;
-        }
+            if digits.is_empty() {
+                return Err(self.report_at(ReportKind::NaNError, start));
+            }
+            if digits.chars().any(|c| c != '0' && c != '1') {
+                return Err(self.report_at(ReportKind::BinaryTerminalError, start));
+            }
 
-        // check for binary
-        if let Some(last) = self.tokens.last() {
-            if last.length != 7 {
-                return Err(Report::new(
-                    ReportKind::SevenBitsError,
-                    Some(self.token_end.clone()),
-                    self.current_line.into(),
-                ));
+            if self.config.extended {
+                self.check_terminal_value(digits, 2, start)?;
+            } else if digits.len() != 7 {
+                let report = self.report_at(ReportKind::SevenBitsError, start);
+                self.reports.push(report);
             }
-            for c in last.get_lexeme().chars() {
-                if c != '0' && c != '1' {
-                    return Err(Report::new(
-                        ReportKind::BinaryTerminalError,
-                        Some(self.token_end.clone()),
-                        self.current_line.into(),
-                    ));
-                }
+
+            if !self.lex_num_val_separator()? {
+                return Ok(());
             }
-        } else {
-            return Err(Report::new(
-                ReportKind::InternalLexerError,
-                None,
-                self.current_line.into(),
-            ));
         }
-
-        Ok(())
     }
 
     fn lex_terminal_decimal(&mut self) -> LexResult<()> {
-        self.lex_number_literal(TokenKind::Decimal)?;
+        loop {
+            let start = self.token_end.clone();
+            self.lex_number_literal(TokenKind::Decimal)?;
+            let digits = &self.src[start.offset..self.token_end.offset];
 
-        if self.next_is('-') || self.next_is('.') {
-            self.lex_single(TokenKind::Range)?;
-            self.lex_terminal_decimal()?;
-        }
+            if digits.is_empty() {
+                return Err(self.report_at(ReportKind::NaNError, start));
+            }
 
-        if let Some(last) = self.tokens.last() {
-            let n = last.get_lexeme().parse::<i32>();
-
-            match n {
-                Ok(num) => {
-                    if num < 0 || num > 126 && !self.config.extended {
-                        return Err(Report::new(
-                            ReportKind::DecimalTerminalError,
-                            Some(self.token_end.clone()),
-                            self.current_line.into(),
-                        ));
-                    }
-                }
-                Err(_err) => {
-                    return Err(Report::new(
-                        ReportKind::NaNError,
-                        Some(self.token_end.clone()),
-                        self.current_line.into(),
-                    ))
-                }
+            if self.config.extended {
+                self.check_terminal_value(digits, 10, start)?;
+            } else if digits.parse::<u32>().map_or(true, |value| value > 126) {
+                let report = self.report_at(ReportKind::DecimalTerminalError, start);
+                self.reports.push(report);
             }
-        } else {
-            return Err(Report::new(
-                ReportKind::InternalLexerError,
-                None,
-                self.current_line.into(),
-            ));
-        }
 
-        Ok(())
+            if !self.lex_num_val_separator()? {
+                return Ok(());
+            }
+        }
     }
 
     fn lex_terminal_hexadecimal(&mut self) -> LexResult<()> {
-        let look_ahead = self.rest();
-
-        if look_ahead.len() < 2 {
-            return Err(Report::new(
-                ReportKind::EofError,
-                Some(self.token_end.clone()),
-                self.current_line.into(),
-            ));
-        }
-        match u32::from_str_radix(&look_ahead[..2], 16) {
-            Ok(hex) => {
-                self.advance()?;
+        loop {
+            // exactly two digits, any number of them in extended mode
+            let start = self.token_end.clone();
+            while self.next.is_some_and(|c| c.is_ascii_hexdigit())
+                && (self.config.extended || self.token_end.offset - start.offset < 2)
+            {
                 self.advance()?;
+            }
+            let digits = &self.src[start.offset..self.token_end.offset];
 
-                if self.config.extended {
-                    loop {
-                        if let Some(n) = self.next {
-                            match u32::from_str_radix(&n.to_string(), 16) {
-                                Ok(_) => self.advance()?,
-                                Err(_) => break,
-                            }
-                        } else {
-                            break;
-                        }
-                    }
-                }
-                self.add_token(TokenKind::Hexadecimal);
+            if digits.is_empty() || !self.config.extended && digits.len() != 2 {
+                return Err(self.report_at(ReportKind::NaHexNError, start));
+            }
+            self.add_token(TokenKind::Hexadecimal);
 
-                if self.next_is('-') || self.next_is('.') {
-                    self.lex_single(TokenKind::Range)?;
-                    self.lex_terminal_hexadecimal()?;
-                }
+            if self.config.extended {
+                self.check_terminal_value(digits, 16, start)?;
+            } else if u32::from_str_radix(digits, 16).is_ok_and(|value| value > 126) {
+                let report = self.report_at(ReportKind::HexadecimalTerminalError, start);
+                self.reports.push(report);
+            }
 
-                if hex > 126 && !self.config.extended {
-                    return Err(Report::new(
-                        ReportKind::HexadecimalTerminalError,
-                        Some(self.token_end.clone()),
-                        self.current_line.into(),
-                    ));
-                }
+            if !self.lex_num_val_separator()? {
+                return Ok(());
             }
-            Err(_err) => {
-                return Err(Report::new(
-                    ReportKind::NaHexNError,
-                    Some(self.token_end.clone()),
-                    self.current_line.into(),
-                ))
+        }
+    }
+
+    // extended terminals are values of the grammar's encoding, in UTF-8
+    // they are code points so surrogates are not allowed
+    fn check_terminal_value(&self, digits: &str, radix: u32, start: Position) -> LexResult<()> {
+        let max = self.encoding.max_value();
+        let report = match u32::from_str_radix(digits, radix) {
+            Err(_) => self.report_at(ReportKind::TerminalOverflowError, start),
+            Ok(value) if value > max => self
+                .report_at(ReportKind::TerminalRangeError, start)
+                .note(format!("{:?} values go up to {max:X}", self.encoding)),
+            Ok(0xD800..=0xDFFF) if self.encoding == Encoding::Utf8 => {
+                self.report_at(ReportKind::SurrogateTerminalError, start)
             }
+            Ok(_) => return Ok(()),
         };
 
-        Ok(())
+        Err(report)
+    }
+
+    // report about the source from `pos` up to the cursor
+    fn report_at(&self, kind: ReportKind, pos: Position) -> Report {
+        Report::from_span(kind, self.src, Span::new(pos.offset, self.token_end.offset))
+    }
+
+    // `-` between two values is a range, `.` a concatenation of values
+    fn lex_num_val_separator(&mut self) -> LexResult<bool> {
+        let kind = match self.next {
+            Some('-') => TokenKind::Range,
+            Some('.') => TokenKind::Dot,
+            _ => return Ok(false),
+        };
+
+        self.lex_single(kind)?;
+        Ok(true)
     }
 
     fn lex_assignment(&mut self) -> LexResult<()> {
@@ -366,13 +504,15 @@ impl<'s> Lexer<'s> {
 
     fn lex_comment(&mut self) -> LexResult<()> {
         while let Some(peeked) = self.next {
-            if peeked != '\n' && !self.is_at_end() {
+            if peeked != '\n' && peeked != '\r' && !self.is_at_end() {
                 self.advance()?;
             } else {
                 break;
             }
         }
 
+        self.add_token(TokenKind::Comment);
+
         Ok(())
     }
 
@@ -390,6 +530,14 @@ impl<'s> Lexer<'s> {
             self.advance()?;
         }
 
+        // c-nl WSP continues the current rule, a bare c-nl ends it unless
+        // brackets are still open, as in a closing `)` on its own line
+        if self.next_is_whitespace() || !self.open_brackets.is_empty() {
+            self.add_token(TokenKind::Continuation);
+        } else {
+            self.add_token(TokenKind::Newline);
+        }
+
         // update current line for nicely reporting errors
         if let Some(index) = self.src[self.token_end.offset..].find('\n') {
             self.current_line = &self.src[self.token_end.offset..index + self.token_end.offset]
@@ -408,12 +556,18 @@ impl<'s> Lexer<'s> {
     }
 
     fn lex_whitespace(&mut self) -> LexResult<()> {
+        self.skip_whitespace()?;
+
+        self.add_token(TokenKind::Whitespace);
+
+        Ok(())
+    }
+
+    fn skip_whitespace(&mut self) -> LexResult<()> {
         while self.next_is_whitespace() {
             self.advance()?;
         }
 
-        self.add_token(TokenKind::Whitespace);
-
         Ok(())
     }
 
@@ -423,8 +577,6 @@ impl<'s> Lexer<'s> {
             TokenKind::RightParen => self.close_bracket(TokenKind::Paren)?,
             TokenKind::LeftSquare => self.open_bracket(TokenKind::Square),
             TokenKind::RightSquare => self.close_bracket(TokenKind::Square)?,
-            TokenKind::LeftAngle => self.open_bracket(TokenKind::Angle),
-            TokenKind::RightAngle => self.close_bracket(TokenKind::Angle)?,
             _ => {
                 return Err(Report::new(
                     ReportKind::InternalLexerError,
@@ -437,30 +589,72 @@ impl<'s> Lexer<'s> {
         self.lex_single(kind)
     }
 
+    // where the rule with the bracket opened at `open` ends, before trailing
+    // comments, and the brackets that close every bracket still open there
+    fn missing_brackets(&self, open: usize) -> (usize, String) {
+        // line breaks are continuations while brackets are open, the next rule
+        // starts with a rule name right after one
+        let tokens = &self.tokens;
+        let start = tokens
+            .iter()
+            .position(|token| token.pos.offset > open)
+            .unwrap_or(tokens.len());
+        let rule_end = (start..tokens.len())
+            .find(|&i| match tokens[i].kind {
+                TokenKind::Newline | TokenKind::EOF => true,
+                TokenKind::Continuation => tokens
+                    .get(i + 1)
+                    .is_some_and(|next| next.kind == TokenKind::Identifier),
+                _ => false,
+            })
+            .unwrap_or(tokens.len());
+
+        let end = tokens[start..rule_end]
+            .iter()
+            .rev()
+            .find(|token| {
+                !matches!(
+                    token.kind,
+                    TokenKind::Whitespace | TokenKind::Comment | TokenKind::Continuation
+                )
+            })
+            .map_or(open + 1, |token| token.pos.offset + token.length);
+
+        let closing = self
+            .open_brackets
+            .iter()
+            .rev()
+            .filter(|(_, pos)| pos.offset < end)
+            .map(|(kind, _)| match kind {
+                TokenKind::Paren => ')',
+                _ => ']',
+            })
+            .collect();
+
+        (end, closing)
+    }
+
     fn open_bracket(&mut self, kind: TokenKind) {
-        self.open_brackets.push(kind)
+        self.open_brackets.push((kind, self.token_end.clone()))
     }
 
     fn close_bracket(&mut self, kind: TokenKind) -> LexResult<()> {
+        let before = self.token_end.clone();
         match self.open_brackets.pop() {
-            Some(open) if open == kind => Ok(()),
-            Some(_) => {
-                let before = self.token_end.clone();
+            Some((open, _)) if open == kind => Ok(()),
+            Some((_, open)) => {
                 self.lex_single(kind)?;
-                return Err(Report::new(
-                    ReportKind::MismatchedClosingBracketError,
-                    Some(before),
-                    self.current_line.into(),
-                ));
+                Err(self
+                    .report_at(ReportKind::MismatchedClosingBracketError, before)
+                    .label(
+                        self.src,
+                        Span::new(open.offset, open.offset + 1),
+                        "bracket opened here",
+                    ))
             }
             None => {
-                let before = self.token_end.clone();
                 self.lex_single(kind)?;
-                Err(Report::new(
-                    ReportKind::UnexpectedClosingBracketError,
-                    Some(before),
-                    self.current_line.into(),
-                ))
+                Err(self.report_at(ReportKind::UnexpectedClosingBracketError, before))
             }
         }
     }
@@ -471,7 +665,7 @@ impl<'s> Lexer<'s> {
                 let len_utf8 = c.len_utf8();
 
                 self.token_end.offset += len_utf8;
-                self.token_end.column += len_utf8; // test to see if this should be 1
+                self.token_end.column += 1;
 
                 self.next = self.chars.next();
 
@@ -517,10 +711,6 @@ impl<'s> Lexer<'s> {
         &self.src[self.token_end.offset..]
     }
 
-    fn peek_rest(&self, prefix: &str) -> bool {
-        self.rest().starts_with(prefix)
-    }
-
     fn is_at_end(&self) -> bool {
         self.rest().is_empty()
     }
@@ -568,7 +758,20 @@ mod tests {
 
                 test($text, None, Some(kinds), true);
             }
-        }
+        };
+        {
+            name:     $name:ident,
+            text:     $text:expr,
+            extended: true,
+            errors:   ($($kind:expr),*)
+        } => {
+            #[test]
+            fn $name() {
+                let kinds: &[ReportKind] = &[$($kind,)*];
+
+                test_with($text, LexerConfig { extended: true }, None, Some(kinds), true);
+            }
+        };
     }
 
     fn test(
@@ -577,10 +780,26 @@ mod tests {
         want_report_kinds: Option<&[ReportKind]>,
         expect_error: bool,
     ) {
-        let mut lexer = Lexer::new(text);
+        test_with(
+            text,
+            LexerConfig::default(),
+            want_kinds,
+            want_report_kinds,
+            expect_error,
+        )
+    }
+
+    fn test_with(
+        text: &str,
+        config: LexerConfig,
+        want_kinds: Option<&[TokenKind]>,
+        want_report_kinds: Option<&[ReportKind]>,
+        expect_error: bool,
+    ) {
+        let mut lexer = Lexer::new(text, config, Encoding::default());
 
         match lexer.tokenize() {
-            Ok(tokens) => {
+            Ok((tokens, reports)) if reports.is_empty() => {
                 let have_kinds = tokens
                     .iter()
                     .map(|t| t.kind.clone())
@@ -589,7 +808,7 @@ mod tests {
                 // safe unwrap as it is a guarantee
                 assert_eq!(have_kinds, want_kinds.unwrap(), "{have_kinds:?}");
             }
-            Err(err) => {
+            Ok((_, err)) | Err(err) => {
                 if !expect_error {
                     for e in err {
                         println!("{e}");
@@ -625,9 +844,45 @@ mod tests {
     }
 
     test! {
-        name: char_delim_angle,
-        text: "<>",
-        tokens: (TokenKind::LeftAngle, TokenKind::RightAngle)
+        name: repeats,
+        text: "3*5a *b 2*c *4d 7e",
+        tokens: (
+            TokenKind::Repeat { min: 3, max: Some(5) }, TokenKind::Identifier, TokenKind::Whitespace,
+            TokenKind::Repeat { min: 0, max: None }, TokenKind::Identifier, TokenKind::Whitespace,
+            TokenKind::Repeat { min: 2, max: None }, TokenKind::Identifier, TokenKind::Whitespace,
+            TokenKind::Repeat { min: 0, max: Some(4) }, TokenKind::Identifier, TokenKind::Whitespace,
+            TokenKind::Repeat { min: 7, max: Some(7) }, TokenKind::Identifier
+        )
+    }
+
+    error! {
+        name: repeat_whitespace,
+        text: "3 * 5a",
+        errors: (ReportKind::RepeatWhitespaceError)
+    }
+
+    error! {
+        name: inverted_repeat,
+        text: "5*3a",
+        errors: (ReportKind::InvertedRepeatError)
+    }
+
+    test! {
+        name: prose,
+        text: "<any text, even \"quotes\" or ; and ( >",
+        tokens: (TokenKind::Prose)
+    }
+
+    error! {
+        name: unterminated_prose,
+        text: "a = <prose\nb = c",
+        errors: (ReportKind::UnterminatedProseError)
+    }
+
+    error! {
+        name: prose_character_error,
+        text: "<tab\tor>",
+        errors: (ReportKind::ProseCharacterError)
     }
 
     test! {
@@ -645,7 +900,7 @@ mod tests {
     test! {
         name: char_star,
         text: "*",
-        tokens: (TokenKind::Star)
+        tokens: (TokenKind::Repeat { min: 0, max: None })
     }
 
     test! {
@@ -675,13 +930,60 @@ mod tests {
     test! {
         name: newline_safe,
         text: "*\n-\n=/\n=",
-        tokens: (TokenKind::Star, TokenKind::Range, TokenKind::EqualSlash, TokenKind::Equal)
+        tokens: (
+            TokenKind::Repeat { min: 0, max: None },
+            TokenKind::Newline,
+            TokenKind::Range,
+            TokenKind::Newline,
+            TokenKind::EqualSlash,
+            TokenKind::Newline,
+            TokenKind::Equal
+        )
     }
 
     test! {
-        name: comment_ignored,
-        text: "=; = = * *\n*",
-        tokens: (TokenKind::Equal, TokenKind::Star)
+        name: newline_crlf,
+        text: "*\r\n*",
+        tokens: (TokenKind::Repeat { min: 0, max: None }, TokenKind::Newline, TokenKind::Repeat { min: 0, max: None })
+    }
+
+    test! {
+        name: newline_continuation,
+        text: "a =\n  b\n",
+        tokens: (
+            TokenKind::Identifier,
+            TokenKind::Whitespace,
+            TokenKind::Equal,
+            TokenKind::Continuation,
+            TokenKind::Whitespace,
+            TokenKind::Identifier,
+            TokenKind::Newline
+        )
+    }
+
+    test! {
+        name: newline_in_brackets_continues,
+        text: "(\n)",
+        tokens: (TokenKind::LeftParen, TokenKind::Continuation, TokenKind::RightParen)
+    }
+
+    test! {
+        name: comment,
+        text: "=; = = * *\r\n*",
+        tokens: (TokenKind::Equal, TokenKind::Comment, TokenKind::Newline, TokenKind::Repeat { min: 0, max: None })
+    }
+
+    #[test]
+    fn lossless_round_trip() {
+        for source in [include_str!("../cln.abnf"), "a = b ; c\r\n  / d\n\n; e\n"] {
+            let (tokens, _) =
+                Lexer::new(source, LexerConfig { extended: true }, Encoding::default())
+                    .tokenize()
+                    .unwrap();
+
+            let text = tokens.iter().map(|t| t.get_lexeme()).collect::<String>();
+            assert_eq!(text, source);
+        }
     }
 
     test! {
@@ -726,6 +1028,18 @@ mod tests {
         tokens: (TokenKind::Mod, TokenKind::TerminalHexadecimal, TokenKind::Hexadecimal)
     }
 
+    test! {
+        name: hex_sequence_and_range,
+        text: "%x0D.0A %x0D-0A",
+        tokens: (
+            TokenKind::Mod, TokenKind::TerminalHexadecimal,
+            TokenKind::Hexadecimal, TokenKind::Dot, TokenKind::Hexadecimal,
+            TokenKind::Whitespace,
+            TokenKind::Mod, TokenKind::TerminalHexadecimal,
+            TokenKind::Hexadecimal, TokenKind::Range, TokenKind::Hexadecimal
+        )
+    }
+
     error! {
         name: hex_terminal_error,
         text: "%x80",
@@ -738,15 +1052,207 @@ mod tests {
         errors: (ReportKind::NaHexNError, ReportKind::NaHexNError)
     }
 
+    test! {
+        name: uppercase_terminals,
+        text: "%B1000001 %D65 %X41 %S\"a\" %I\"a\"",
+        tokens: (
+            TokenKind::Mod, TokenKind::TerminalBinary, TokenKind::Binary, TokenKind::Whitespace,
+            TokenKind::Mod, TokenKind::TerminalDecimal, TokenKind::Decimal, TokenKind::Whitespace,
+            TokenKind::Mod, TokenKind::TerminalHexadecimal, TokenKind::Hexadecimal,
+            TokenKind::Whitespace,
+            TokenKind::Mod, TokenKind::TerminalSensitive, TokenKind::String, TokenKind::Whitespace,
+            TokenKind::Mod, TokenKind::TerminalInsensitive, TokenKind::String
+        )
+    }
+
     test! {
         name: string_literal,
         text: "\"string\"",
         tokens: (TokenKind::String)
     }
 
+    test! {
+        name: case_sensitive_string,
+        text: "%s\"aB\" %i\"aB\"",
+        tokens: (
+            TokenKind::Mod, TokenKind::TerminalSensitive, TokenKind::String,
+            TokenKind::Whitespace,
+            TokenKind::Mod, TokenKind::TerminalInsensitive, TokenKind::String
+        )
+    }
+
+    error! {
+        name: case_sensitive_without_string,
+        text: "%sab",
+        errors: (ReportKind::ExpectedStringError)
+    }
+
+    error! {
+        name: unexpected_characters,
+        text: "a = b @@ c\nd = e_f",
+        errors: (
+            ReportKind::UnexpectedCharacterError,
+            ReportKind::UnexpectedCharacterError,
+            ReportKind::UnexpectedCharacterError
+        )
+    }
+
+    error! {
+        name: recovers_without_cascading,
+        text: "a = %sab\"c\nb = %b12*3 c\ne = (%x4G) / [%b2] / (f 3 * 4c)\n\
+            d = %x4G \"unterminated",
+        errors: (
+            ReportKind::ExpectedStringError,
+            ReportKind::BinaryTerminalError,
+            ReportKind::NaHexNError,
+            ReportKind::BinaryTerminalError,
+            ReportKind::RepeatWhitespaceError,
+            ReportKind::NaHexNError,
+            ReportKind::UnterminatedStringError
+        )
+    }
+
     error! {
         name: unterminated_string_error,
         text: "\"unterminated string",
         errors: (ReportKind::UnterminatedStringError)
     }
+
+    error! {
+        name: unterminated_string_stops_at_eol,
+        text: "a = \"abc\nb = \"c\"",
+        errors: (ReportKind::UnterminatedStringError)
+    }
+
+    error! {
+        name: string_character_error,
+        text: "\"tab\there\" \"caf\u{e9}\"",
+        errors: (ReportKind::StringCharacterError, ReportKind::StringCharacterError)
+    }
+
+    // values outside of US-ASCII are reported but still lexed, so the config
+    // can turn the reports into warnings
+    #[test]
+    fn ascii_limits_keep_tokens() {
+        let (tokens, reports) = Lexer::new("%d200", LexerConfig::default(), Encoding::default())
+            .tokenize()
+            .unwrap();
+
+        let kinds = tokens.iter().map(|t| t.kind.clone()).collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            [
+                TokenKind::Mod,
+                TokenKind::TerminalDecimal,
+                TokenKind::Decimal,
+                TokenKind::EOF
+            ]
+        );
+        assert_eq!(reports[0].get_kind(), ReportKind::DecimalTerminalError);
+    }
+
+    #[test]
+    fn extended_string() {
+        let mut lexer = Lexer::new(
+            "\"caf\u{e9}\"",
+            LexerConfig { extended: true },
+            Encoding::default(),
+        );
+        assert!(lexer
+            .tokenize()
+            .is_ok_and(|(_, reports)| reports.is_empty()));
+
+        let mut lexer = Lexer::new(
+            "\"a\tb\"",
+            LexerConfig { extended: true },
+            Encoding::default(),
+        );
+        assert!(lexer
+            .tokenize()
+            .is_ok_and(|(_, reports)| !reports.is_empty()));
+    }
+
+    error! {
+        name: code_point_overflow,
+        text: "%xFFFFFFFFF\n%d99999999999",
+        extended: true,
+        errors: (ReportKind::TerminalOverflowError, ReportKind::TerminalOverflowError)
+    }
+
+    error! {
+        name: surrogate_code_points,
+        text: "%xD800\n%d57343\n%b1101100000000000",
+        extended: true,
+        errors: (
+            ReportKind::SurrogateTerminalError,
+            ReportKind::SurrogateTerminalError,
+            ReportKind::SurrogateTerminalError
+        )
+    }
+
+    error! {
+        name: code_point_out_of_range,
+        text: "%x110000\n%x41-110000",
+        extended: true,
+        errors: (ReportKind::TerminalRangeError, ReportKind::TerminalRangeError)
+    }
+
+    #[test]
+    fn encodings() {
+        let text = "%x0-FF %xD800";
+        let kinds = |encoding| match Lexer::new(text, LexerConfig { extended: true }, encoding)
+            .tokenize()
+        {
+            Ok((_, err)) | Err(err) => err.iter().map(|r| r.get_kind()).collect::<Vec<_>>(),
+        };
+
+        assert_eq!(kinds(Encoding::Bytes), [ReportKind::TerminalRangeError]);
+        assert_eq!(kinds(Encoding::Utf8), [ReportKind::SurrogateTerminalError]);
+        assert_eq!(kinds(Encoding::Utf16), []);
+    }
+
+    #[test]
+    fn extended_terminals() {
+        let text = "%x0-10FFFF %d1114111 %b1 %b100001";
+        let mut lexer = Lexer::new(text, LexerConfig { extended: true }, Encoding::default());
+        assert!(lexer
+            .tokenize()
+            .is_ok_and(|(_, reports)| reports.is_empty()));
+    }
+
+    #[test]
+    fn closes_unclosed_brackets() {
+        let fix = |text| {
+            let err = Lexer::new(text, LexerConfig::default(), Encoding::default())
+                .tokenize()
+                .unwrap_err();
+            let edit = err[0].edit().unwrap();
+            crate::fix::apply(text, std::slice::from_ref(edit)).0
+        };
+
+        assert_eq!(fix("a = (b / [c"), "a = (b / [c])");
+        assert_eq!(
+            fix("a = (b ; comment\n  c\nd = e"),
+            "a = (b ; comment\n  c)\nd = e"
+        );
+        assert_eq!(fix("a = [b\n]\nc = (d"), "a = [b\n]\nc = (d)");
+
+        // source dropped after an error has no fix
+        let err = Lexer::new("a = (b %x4G", LexerConfig::default(), Encoding::default())
+            .tokenize()
+            .unwrap_err();
+        assert_eq!(err[1].get_kind(), ReportKind::UnclosedBracketError);
+        assert!(err[1].edit().is_none());
+    }
+
+    #[test]
+    fn unexpected_character_fails() {
+        // the tokens would read `b c`, the parser must not see them
+        let err = Lexer::new("a = b@c", LexerConfig::default(), Encoding::default())
+            .tokenize()
+            .unwrap_err();
+
+        assert_eq!(err.len(), 1);
+        assert_eq!(err[0].get_kind(), ReportKind::UnexpectedCharacterError);
+    }
 }
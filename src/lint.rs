@@ -0,0 +1,638 @@
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+
+use serde::Deserialize;
+use strum::IntoEnumIterator;
+use strum_macros::{Display, EnumIter, EnumString};
+
+use crate::config::RuleCase;
+use crate::expr::{Expr, ExprKind, NumVal, Radix};
+use crate::grammar::{Grammar, Name, Rule};
+use crate::position::Position;
+use crate::report::Report;
+use crate::report_kind::ReportKind;
+use crate::severity::Severity;
+use crate::span::Span;
+
+// marks a comment that allows lints, e.g. `; abnf-toolkit: allow(unused-rule)`
+const DIRECTIVE: &str = "abnf-toolkit:";
+
+/// style checks on a parsed grammar, named in kebab-case in the `[lint]`
+/// table of the config and in `allow` comments
+#[derive(Deserialize, Display, EnumIter, EnumString, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "kebab-case")]
+pub enum Lint {
+    NamingStyle,
+    UnusedRule,
+    ProseVal,
+    BroadRange,
+    RedundantGroup,
+    EmptyAlternative,
+    LowercaseHex,
+    CoreRuleValue,
+}
+
+/// what is done with the reports of a lint
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    /// not reported
+    Allow,
+    /// reported as a warning
+    Warn,
+    /// reported as an error
+    Deny,
+}
+
+impl Lint {
+    pub fn kind(&self) -> ReportKind {
+        match self {
+            Lint::NamingStyle => ReportKind::InconsistentCasingWarning,
+            Lint::UnusedRule => ReportKind::UnreachableRuleWarning,
+            Lint::ProseVal => ReportKind::ProseValWarning,
+            Lint::BroadRange => ReportKind::BroadRangeWarning,
+            Lint::RedundantGroup => ReportKind::RedundantGroupWarning,
+            Lint::EmptyAlternative => ReportKind::EmptyAlternativeWarning,
+            Lint::LowercaseHex => ReportKind::LowercaseHexWarning,
+            Lint::CoreRuleValue => ReportKind::CoreRuleValueWarning,
+        }
+    }
+
+    /// level of the lint unless the config overrides it
+    pub fn level(&self) -> Level {
+        Level::Warn
+    }
+}
+
+/// runs every lint on a grammar, the first rule is the start rule
+pub struct Linter<'g, 's> {
+    src: &'s str,
+    grammar: &'g Grammar,
+
+    definitions: HashMap<Name<'g>, Vec<&'g Rule>>,
+    core_rules: HashSet<Name<'g>>,
+    rule_case: RuleCase,
+
+    // overrides from the config
+    levels: HashMap<Lint, Level>,
+    // lints allowed by comments, by the line they apply to, 0 for the file
+    allowed: HashSet<(usize, Lint)>,
+
+    reports: Vec<Report>,
+}
+
+impl<'g, 's> Linter<'g, 's> {
+    pub fn new(
+        source: &'s str,
+        grammar: &'g Grammar,
+        core_rules: Option<&'g Grammar>,
+        rule_case: RuleCase,
+        levels: HashMap<Lint, Level>,
+    ) -> Self {
+        let core_rules = core_rules
+            .into_iter()
+            .flat_map(|core| &core.rules)
+            .map(|rule| Name(&rule.name))
+            .collect();
+
+        Linter {
+            src: source,
+            grammar,
+            definitions: grammar.definitions(),
+            core_rules,
+            rule_case,
+            levels,
+            allowed: HashSet::new(),
+            reports: Vec::new(),
+        }
+    }
+
+    /// every report of a lint that is not allowed
+    pub fn lint(&mut self) -> Vec<Report> {
+        self.allow();
+        self.lint_unused();
+        for rule in &self.grammar.rules {
+            self.lint_expr(&rule.expr, None);
+        }
+        self.lint_naming();
+
+        std::mem::take(&mut self.reports)
+    }
+
+    // lints named in `allow(...)` comments apply to the line after the
+    // comment, those in `allow-file(...)` comments to the whole file. only
+    // comments count, a `;` in a string or prose is not one
+    fn allow(&mut self) {
+        for comment in &self.grammar.comments {
+            // skip the `;`
+            let text = &self.src[comment.start + 1..comment.end];
+            let Some(directive) = text.trim_start().strip_prefix(DIRECTIVE) else {
+                continue;
+            };
+
+            let line = Position::from_offset(self.src, comment.start).line;
+            let directive = directive.trim_start();
+            let (applies_to, lints) = if let Some(lints) = directive.strip_prefix("allow-file(") {
+                (0, lints)
+            } else if let Some(lints) = directive.strip_prefix("allow(") {
+                (line + 1, lints)
+            } else {
+                continue;
+            };
+
+            // `lints` runs up to the end of the comment
+            let mut offset = comment.end - lints.len();
+            let lints = lints.split(')').next().unwrap_or_default();
+            for name in lints.split(',') {
+                let start = offset + name.len() - name.trim_start().len();
+                offset += name.len() + 1;
+
+                let name = name.trim();
+                match Lint::from_str(name) {
+                    Ok(lint) => {
+                        self.allowed.insert((applies_to, lint));
+                    }
+                    Err(_) if !name.is_empty() => {
+                        let span = Span::new(start, start + name.len());
+                        let report =
+                            Report::from_span(ReportKind::UnknownLintWarning, self.src, span).note(
+                                format!(
+                                    "the lints are {}",
+                                    Lint::iter()
+                                        .map(|lint| format!("`{lint}`"))
+                                        .collect::<Vec<_>>()
+                                        .join(", ")
+                                ),
+                            );
+                        self.reports.push(report);
+                    }
+                    Err(_) => {}
+                }
+            }
+        }
+    }
+
+    fn emit(&mut self, lint: Lint, report: Report) {
+        let level = self.levels.get(&lint).copied().unwrap_or(lint.level());
+        let line = report.pos().map_or(0, |pos| pos.line);
+        if level == Level::Allow
+            || self.allowed.contains(&(0, lint))
+            || self.allowed.contains(&(line, lint))
+        {
+            return;
+        }
+
+        let mut report = report;
+        if level == Level::Deny {
+            report.set_severity(Severity::Error);
+        }
+        self.reports.push(report);
+    }
+
+    fn report(&self, lint: Lint, expr: &Expr) -> Report {
+        Report::from_span(lint.kind(), self.src, expr.span)
+    }
+
+    // rules that are never reached from the start rule
+    fn lint_unused(&mut self) {
+        let Some(start) = self.grammar.rules.first() else {
+            return;
+        };
+
+        let mut reachable: HashSet<Name> = HashSet::from([Name(&start.name)]);
+        let mut queue = vec![Name(&start.name)];
+
+        while let Some(name) = queue.pop() {
+            for rule in self.definitions.get(&name).into_iter().flatten() {
+                rule.expr.walk(&mut |expr| {
+                    if let ExprKind::Rule(name) = &expr.kind {
+                        if reachable.insert(Name(name)) {
+                            queue.push(Name(name));
+                        }
+                    }
+                });
+            }
+        }
+
+        // report each unreachable rule once, at its first definition
+        let mut reported: HashSet<Name> = HashSet::new();
+        for rule in &self.grammar.rules {
+            let name = Name(&rule.name);
+            if !reachable.contains(&name) && reported.insert(name) {
+                let report =
+                    Report::from_span(ReportKind::UnreachableRuleWarning, self.src, rule.name_span);
+                self.emit(Lint::UnusedRule, report);
+            }
+        }
+    }
+
+    // lints of single expressions, `parent` is None for the body of a rule
+    fn lint_expr(&mut self, expr: &Expr, parent: Option<&ExprKind>) {
+        match &expr.kind {
+            // prose is only meant for humans, tools have to skip over it
+            ExprKind::ProseVal(_) => self.emit(Lint::ProseVal, self.report(Lint::ProseVal, expr)),
+            ExprKind::NumVal { radix, value } => self.lint_num_val(expr, radix, value),
+            ExprKind::Group(inner) if redundant_group(inner, parent) => {
+                let mut report = self
+                    .report(Lint::RedundantGroup, expr)
+                    .help("remove the parentheses");
+                // only whitespace around the grouped expression, or comments
+                // would be lost
+                let before = &self.src[expr.span.start + 1..inner.span.start];
+                let after = &self.src[inner.span.end..expr.span.end - 1];
+                let repeated = matches!(parent, Some(ExprKind::Repetition { .. }));
+                // and no repeat prefix right after another, `*(3"b")` is not
+                // `*3"b"`
+                let merges = repeated
+                    && self.src[inner.span.start..]
+                        .starts_with(|c: char| c == '*' || c.is_ascii_digit());
+                if before.trim().is_empty() && after.trim().is_empty() && !merges {
+                    let ungrouped = ungrouped(self.src, expr.span, inner.span, repeated);
+                    report = report.fix(expr.span, ungrouped);
+                }
+                self.emit(Lint::RedundantGroup, report);
+            }
+            ExprKind::Alternation(alternatives) => {
+                for alternative in alternatives.iter().filter(|alt| matches_only_empty(alt)) {
+                    let report = self
+                        .report(Lint::EmptyAlternative, alternative)
+                        .help("make the other alternatives optional with `[...]` instead");
+                    self.emit(Lint::EmptyAlternative, report);
+                }
+            }
+            _ => {}
+        }
+
+        for child in expr.children() {
+            self.lint_expr(child, Some(&expr.kind));
+        }
+    }
+
+    fn lint_num_val(&mut self, expr: &Expr, radix: &Radix, value: &NumVal) {
+        if let Some(name) = core_rule_value(value) {
+            if self.core_rules.contains(&Name(name)) && !self.definitions.contains_key(&Name(name))
+            {
+                let report = self
+                    .report(Lint::CoreRuleValue, expr)
+                    .help(format!("use the core rule `{name}`"))
+                    .fix(expr.span, name);
+                self.emit(Lint::CoreRuleValue, report);
+            }
+        }
+
+        if let NumVal::Range(0, max) = value {
+            if *max >= 0x7F {
+                let report = self
+                    .report(Lint::BroadRange, expr)
+                    .help("leave out the control characters, e.g. `%x20-7E` for printable ASCII");
+                self.emit(Lint::BroadRange, report);
+            }
+        }
+
+        // digits are after `%x`
+        let text = &self.src[expr.span.start..expr.span.end];
+        if *radix == Radix::Hexadecimal && text[2..].contains(|c: char| c.is_ascii_lowercase()) {
+            let uppercase = format!("{}{}", &text[..2], text[2..].to_ascii_uppercase());
+            let report = self
+                .report(Lint::LowercaseHex, expr)
+                .help(format!("write it as `{uppercase}`"))
+                .fix(expr.span, uppercase);
+            self.emit(Lint::LowercaseHex, report);
+        }
+    }
+
+    // rulenames are case-insensitive, report every definition and reference
+    // that is spelled differently than the rule should be
+    fn lint_naming(&mut self) {
+        let mut names = Vec::new();
+        for rule in &self.grammar.rules {
+            names.push((rule.name.as_str(), rule.name_span));
+            rule.expr.walk(&mut |expr| {
+                if let ExprKind::Rule(name) = &expr.kind {
+                    names.push((name.as_str(), expr.span));
+                }
+            });
+        }
+
+        for (name, span) in names {
+            // undefined rules are reported by the checker
+            let Some(spelling) = self.spelling(name) else {
+                continue;
+            };
+
+            if name != spelling {
+                let report =
+                    Report::from_span(ReportKind::InconsistentCasingWarning, self.src, span)
+                        .help(format!("write it as `{spelling}`"));
+                self.emit(Lint::NamingStyle, report);
+            }
+        }
+    }
+
+    // how `name` should be spelled, rules of the grammar follow the configured
+    // casing and core rules their own spelling
+    fn spelling(&self, name: &str) -> Option<String> {
+        let Some(rules) = self.definitions.get(&Name(name)) else {
+            return self
+                .core_rules
+                .get(&Name(name))
+                .map(|core| core.0.to_string());
+        };
+
+        let first = &rules[0].name;
+        Some(match self.rule_case {
+            RuleCase::Consistent => first.clone(),
+            RuleCase::Lowercase => first.to_ascii_lowercase(),
+            RuleCase::Uppercase => first.to_ascii_uppercase(),
+        })
+    }
+}
+
+// parentheses only change the meaning of a sequence of alternatives or
+// elements, and only where it is nested in something that binds tighter. a
+// repetition of a repetition needs them too, `*(3"b")` is not `*3"b"`
+fn redundant_group(inner: &Expr, parent: Option<&ExprKind>) -> bool {
+    !matches!(
+        (&inner.kind, parent),
+        (
+            ExprKind::Alternation(_) | ExprKind::Concatenation(_) | ExprKind::Repetition { .. },
+            Some(ExprKind::Repetition { .. })
+        ) | (ExprKind::Alternation(_), Some(ExprKind::Concatenation(_)))
+    )
+}
+
+// the source of `inner` in place of the group around it. a space takes the
+// place of a parenthesis that kept it apart from a neighbouring token, `b(c)`
+// is not `bc`, except for the repeat prefix of the group, which `inner` has to
+// follow right away
+fn ungrouped(src: &str, group: Span, inner: Span, repeated: bool) -> String {
+    let separates = |c: Option<char>| {
+        c.is_none_or(|c| {
+            c.is_ascii_whitespace() || matches!(c, '(' | ')' | '[' | ']' | '/' | ';' | '=')
+        })
+    };
+
+    let mut ungrouped = src[inner.start..inner.end].to_string();
+    if !repeated && !separates(src[..group.start].chars().next_back()) {
+        ungrouped.insert(0, ' ');
+    }
+    if !separates(src[group.end..].chars().next()) {
+        ungrouped.push(' ');
+    }
+
+    ungrouped
+}
+
+// the core rule that matches exactly `value`, only for values that are more
+// than a single character
+fn core_rule_value(value: &NumVal) -> Option<&'static str> {
+    match value {
+        NumVal::Sequence(values) if values[..] == [0x0D, 0x0A] => Some("CRLF"),
+        NumVal::Range(0x30, 0x39) => Some("DIGIT"),
+        NumVal::Range(0x21, 0x7E) => Some("VCHAR"),
+        _ => None,
+    }
+}
+
+// whether `expr` can only ever match the empty string
+fn matches_only_empty(expr: &Expr) -> bool {
+    match &expr.kind {
+        ExprKind::CharVal { value, .. } => value.is_empty(),
+        ExprKind::Repetition { max: Some(0), .. } => true,
+        ExprKind::Concatenation(items) => items.iter().all(matches_only_empty),
+        ExprKind::Group(inner) | ExprKind::Option(inner) => matches_only_empty(inner),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::LexerConfig;
+    use crate::core_rules::core_rules;
+    use crate::encoding::Encoding;
+    use crate::fix;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    macro_rules! lint {
+        {
+            name:    $name:ident,
+            text:    $text:expr,
+            reports: ($($kind:expr),*)
+        } => {
+            lint! {
+                name: $name,
+                text: $text,
+                rule_case: RuleCase::default(),
+                reports: ($($kind),*)
+            }
+        };
+        {
+            name:      $name:ident,
+            text:      $text:expr,
+            rule_case: $rule_case:expr,
+            reports:   ($($kind:expr),*)
+        } => {
+            #[test]
+            fn $name() {
+                let kinds: &[ReportKind] = &[$($kind,)*];
+
+                let have_report_kinds = lint($text, $rule_case, HashMap::new())
+                    .iter()
+                    .map(|r| r.get_kind())
+                    .collect::<Vec<_>>();
+                assert_eq!(have_report_kinds, kinds);
+            }
+        };
+    }
+
+    fn lint(text: &str, rule_case: RuleCase, levels: HashMap<Lint, Level>) -> Vec<Report> {
+        let (tokens, _) = Lexer::new(text, LexerConfig::default(), Encoding::default())
+            .tokenize()
+            .expect("test grammar should lex");
+        let grammar = Parser::new(text, &tokens)
+            .parse()
+            .expect("test grammar should parse");
+
+        let core_rules = core_rules();
+        Linter::new(text, &grammar, Some(&core_rules), rule_case, levels).lint()
+    }
+
+    lint! {
+        name: clean_grammar,
+        text: "a = b / *(c d)\nb = \"b\" (c / d)\nc = %x20-7E\nd = [\"d\"]",
+        reports: ()
+    }
+
+    lint! {
+        name: prose,
+        text: "a = b / <some prose>\nb = <more prose>",
+        reports: (ReportKind::ProseValWarning, ReportKind::ProseValWarning)
+    }
+
+    lint! {
+        name: unused_rule,
+        text: "a = b\nb = \"b\"\nc = d\nd = c\nd =/ \"d\"",
+        reports: (ReportKind::UnreachableRuleWarning, ReportKind::UnreachableRuleWarning)
+    }
+
+    lint! {
+        name: case_insensitive_names,
+        text: "a = b / digit\nb = \"b\"\nb =/ \"c\"",
+        reports: (ReportKind::InconsistentCasingWarning)
+    }
+
+    lint! {
+        name: inconsistent_casing,
+        text: "key = Value\nvalue = ALPHA\nValue =/ DIGIT",
+        reports: (ReportKind::InconsistentCasingWarning, ReportKind::InconsistentCasingWarning)
+    }
+
+    lint! {
+        name: lowercase_rules,
+        text: "key = value\nValue = ALPHA",
+        rule_case: RuleCase::Lowercase,
+        reports: (ReportKind::InconsistentCasingWarning)
+    }
+
+    lint! {
+        name: uppercase_rules,
+        text: "KEY = VALUE\nVALUE = ALPHA / digit",
+        rule_case: RuleCase::Uppercase,
+        reports: (ReportKind::InconsistentCasingWarning)
+    }
+
+    lint! {
+        name: broad_range,
+        text: "a = %x00-FF / %x00-1F",
+        reports: (ReportKind::BroadRangeWarning)
+    }
+
+    lint! {
+        name: redundant_group,
+        text: "a = (\"a\") / (b c) / d (e f)\nb = \"b\"\nc = \"c\"\nd = (\"d\" / \"e\")\ne = \"e\"\nf = \"f\"",
+        reports: (
+            ReportKind::RedundantGroupWarning,
+            ReportKind::RedundantGroupWarning,
+            ReportKind::RedundantGroupWarning,
+            ReportKind::RedundantGroupWarning
+        )
+    }
+
+    lint! {
+        name: repeated_repetition,
+        text: "a = *(3\"b\") / 3(*\"c\")",
+        reports: ()
+    }
+
+    lint! {
+        name: empty_alternative,
+        text: "a = \"a\" / \"\" / 0\"b\" / \"c\" \"\"",
+        reports: (ReportKind::EmptyAlternativeWarning, ReportKind::EmptyAlternativeWarning)
+    }
+
+    lint! {
+        name: lowercase_hex,
+        text: "a = %x0d / %x41 / %d13 / %x4a-4F",
+        reports: (ReportKind::LowercaseHexWarning, ReportKind::LowercaseHexWarning)
+    }
+
+    lint! {
+        name: core_rule_value,
+        text: "a = %x0D.0A / %x30-39 / %x0D / %d48-57",
+        reports: (ReportKind::CoreRuleValueWarning, ReportKind::CoreRuleValueWarning, ReportKind::CoreRuleValueWarning)
+    }
+
+    lint! {
+        name: redefined_core_rule_value,
+        text: "a = %x30-39 / DIGIT\nDIGIT = %x30-37",
+        reports: ()
+    }
+
+    // the source with the fixes of every lint applied
+    fn fixed(text: &str) -> String {
+        let reports = lint(text, RuleCase::default(), HashMap::new());
+        let edits = reports
+            .iter()
+            .filter_map(|report| report.edit().cloned())
+            .collect::<Vec<_>>();
+
+        fix::apply(text, &edits).0
+    }
+
+    #[test]
+    fn fixes() {
+        assert_eq!(
+            fixed("a = (\"a\") / b\nb = %x0d.0a"),
+            "a = \"a\" / b\nb = CRLF"
+        );
+        assert_eq!(fixed("a = %x3a-3f"), "a = %x3A-3F");
+        assert_eq!(
+            fixed("a = *( b ) / ( b ; comment\n  )\nb = \"b\""),
+            "a = *b / ( b ; comment\n  )\nb = \"b\""
+        );
+        assert_eq!(
+            fixed("a = *(3\"b\") / 3(*\"c\")"),
+            "a = *(3\"b\") / 3(*\"c\")"
+        );
+        assert_eq!(fixed("a = *((3\"b\"))"), "a = *(3\"b\")");
+
+        // the parser wants whitespace between elements, the parentheses of
+        // `b(c)` still must not join the tokens around them
+        let src = "a = b(c) / %x41(b)";
+        let edits = [
+            fix::Edit {
+                span: Span::new(5, 8),
+                replacement: ungrouped(src, Span::new(5, 8), Span::new(6, 7), false),
+            },
+            fix::Edit {
+                span: Span::new(15, 18),
+                replacement: ungrouped(src, Span::new(15, 18), Span::new(16, 17), false),
+            },
+        ];
+        assert_eq!(fix::apply(src, &edits).0, "a = b c / %x41 b");
+        assert_eq!(fixed("a = 3(b) / (b)\nb = \"b\""), "a = 3b / b\nb = \"b\"");
+    }
+
+    #[test]
+    fn levels() {
+        let text = "a = <prose>\nb = \"b\"";
+
+        let levels = HashMap::from([(Lint::UnusedRule, Level::Allow)]);
+        let reports = lint(text, RuleCase::default(), levels);
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].get_kind(), ReportKind::ProseValWarning);
+
+        let levels = HashMap::from([(Lint::ProseVal, Level::Deny)]);
+        let reports = lint(text, RuleCase::default(), levels);
+        assert_eq!(reports[0].severity(), Severity::Warning);
+        assert_eq!(reports[1].severity(), Severity::Error);
+    }
+
+    lint! {
+        name: allow_comment,
+        text: "a = c\n; abnf-toolkit: allow(unused-rule)\nb = <prose>\n\
+            c = \"c\" ; abnf-toolkit: allow(unused-rule, prose-val)\nd = <prose>",
+        reports: (ReportKind::ProseValWarning)
+    }
+
+    lint! {
+        name: allow_in_string,
+        text: "a = \"; abnf-toolkit: allow(prose-val)\"\n    / <prose>",
+        reports: (ReportKind::ProseValWarning)
+    }
+
+    lint! {
+        name: unknown_lint,
+        text: "; abnf-toolkit: allow-file(prose-val, unused-rules)\na = <prose>",
+        reports: (ReportKind::UnknownLintWarning)
+    }
+
+    lint! {
+        name: allow_file_comment,
+        text: "; abnf-toolkit: allow-file(prose-val)\na = <prose>\nb = <prose>",
+        reports: (ReportKind::UnreachableRuleWarning)
+    }
+}
@@ -1,13 +1,31 @@
+mod check;
 mod cli;
 mod config;
+mod core_rules;
+mod earley;
+mod emitter;
+mod encoding;
+mod explain;
+mod expr;
+mod fix;
+mod forest;
+mod generator;
+mod grammar;
 mod lexer;
+mod lint;
+mod matcher;
+mod parser;
 mod position;
 mod report;
 mod report_kind;
 mod run;
+mod severity;
+mod span;
 mod token;
 mod token_kind;
 
-fn main() {
-    run::run();
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    run::run()
 }
@@ -0,0 +1,366 @@
+use crate::earley::{EarleyGrammar, Symbol};
+use crate::encoding::Encoding;
+use crate::forest::{Forest, Label};
+use crate::grammar::Grammar;
+use crate::report::Report;
+use crate::report_kind::ReportKind;
+use crate::span::Span;
+
+/// decides whether an input is in the language of a grammar rule
+///
+/// built on an Earley recognizer, so any grammar works, including ambiguous
+/// and left recursive ones
+pub struct Matcher<'g, 'i> {
+    // the input as text to point reports into, only for UTF-8
+    text: Option<&'i str>,
+    input: Vec<u32>,
+    // byte offset of every value in `input`, plus the end of the input
+    offsets: Vec<usize>,
+
+    grammar: EarleyGrammar<'g>,
+}
+
+impl<'g, 'i> Matcher<'g, 'i> {
+    pub fn new(
+        grammar: &'g Grammar,
+        core_rules: Option<&'g Grammar>,
+        input: &'i [u8],
+        encoding: Encoding,
+    ) -> Result<Self, Report> {
+        let (values, offsets) = encoding.decode(input).map_err(|offset| {
+            Report::new(ReportKind::InputEncodingError, None, String::new())
+                .note(format!("invalid {encoding:?} at byte offset {offset}"))
+        })?;
+
+        Ok(Matcher {
+            text: match encoding {
+                Encoding::Utf8 => std::str::from_utf8(input).ok(),
+                _ => None,
+            },
+            input: values,
+            offsets,
+            grammar: EarleyGrammar::new(&grammar.expressions(core_rules), encoding),
+        })
+    }
+
+    /// match the whole input against `rule`, returning every derivation of
+    /// it, or the furthest point reached and what was expected there
+    pub fn matches(&self, rule: &str) -> Result<Forest, Report> {
+        let Some(start) = self.grammar.rule(rule) else {
+            return Err(
+                Report::new(ReportKind::UnknownRuleError, None, String::new())
+                    .note(format!("no rule named `{rule}`")),
+            );
+        };
+
+        let chart = self.grammar.recognize(start, &self.input);
+        if self.grammar.derives(
+            &chart,
+            &self.input,
+            Symbol::Nonterminal(start),
+            0,
+            self.input.len(),
+        ) {
+            return Ok(Forest::new(
+                &self.grammar,
+                &chart,
+                &self.input,
+                &self.offsets,
+                start,
+            ));
+        }
+
+        // nothing left to scan at the furthest point means the rule is
+        // complete there but input is left over
+        let furthest = chart.furthest();
+        let expected = self.grammar.expected(&chart, furthest);
+        let note = if expected.is_empty() {
+            "expected end of input".to_string()
+        } else {
+            let expected = expected.into_iter().collect::<Vec<String>>();
+            format!("expected one of {}", expected.join(", "))
+        };
+
+        let offset = self.offsets[furthest];
+        let mut report = self
+            .report_at(ReportKind::InputRejectedError, Span::new(offset, offset))
+            .note(note);
+
+        if self.grammar.reaches_prose(start) {
+            report = report.note(format!(
+                "`{rule}` depends on prose, which never matches any input"
+            ));
+        }
+
+        Err(report)
+    }
+
+    /// a warning for an input with more than one derivation
+    pub fn ambiguity(&self, forest: &Forest) -> Option<Report> {
+        let node = forest.ambiguity()?;
+        let Label::Rule(name) = &node.label else {
+            unreachable!("the root of a forest is always a rule");
+        };
+
+        Some(
+            self.report_at(ReportKind::AmbiguousInputWarning, node.span)
+                .note(format!("`{name}` matches this input in more than one way")),
+        )
+    }
+
+    // binary input can't be shown, so reports only carry the byte offset
+    fn report_at(&self, kind: ReportKind, span: Span) -> Report {
+        match self.text {
+            Some(text) => Report::from_span(kind, text, span),
+            None => Report::new(kind, None, String::new())
+                .note(format!("at byte offset {}", span.start)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::LexerConfig;
+    use crate::core_rules::core_rules;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    macro_rules! test {
+        {
+            name:    $name:ident,
+            grammar: $grammar:expr,
+            input:   $input:expr,
+            matches: $matches:expr
+        } => {
+            #[test]
+            fn $name() {
+                let grammar = parse($grammar);
+                let core_rules = core_rules();
+                let matcher = text_matcher(&grammar, Some(&core_rules), $input);
+
+                let start = grammar.rules[0].name.clone();
+                assert_eq!(matcher.matches(&start).is_ok(), $matches);
+            }
+        };
+    }
+
+    fn text_matcher<'g, 'i>(
+        grammar: &'g Grammar,
+        core_rules: Option<&'g Grammar>,
+        input: &'i str,
+    ) -> Matcher<'g, 'i> {
+        Matcher::new(grammar, core_rules, input.as_bytes(), Encoding::Utf8).unwrap()
+    }
+
+    fn parse(text: &str) -> Grammar {
+        let (tokens, _) = Lexer::new(text, LexerConfig { extended: true }, Encoding::default())
+            .tokenize()
+            .expect("test grammar should lex");
+
+        Parser::new(text, &tokens)
+            .parse()
+            .expect("test grammar should parse")
+    }
+
+    test! {
+        name: char_val_is_case_insensitive,
+        grammar: "a = \"abc\"",
+        input: "aBC",
+        matches: true
+    }
+
+    test! {
+        name: case_sensitive_string,
+        grammar: "a = %s\"aB\" %i\"c\"",
+        input: "aBC",
+        matches: true
+    }
+
+    test! {
+        name: case_sensitive_string_rejects_other_case,
+        grammar: "a = %s\"aB\"",
+        input: "ab",
+        matches: false
+    }
+
+    test! {
+        name: alternation_and_concatenation,
+        grammar: "a = (\"x\" / \"xy\") \"z\"",
+        input: "xyz",
+        matches: true
+    }
+
+    test! {
+        name: repetition_bounds,
+        grammar: "a = 2*3\"x\"",
+        input: "xxxx",
+        matches: false
+    }
+
+    test! {
+        name: repetition_of_optional,
+        grammar: "a = *[\"x\"] \"y\"",
+        input: "xxy",
+        matches: true
+    }
+
+    test! {
+        name: incremental_alternatives,
+        grammar: "a = \"x\"\na =/ \"y\"",
+        input: "y",
+        matches: true
+    }
+
+    test! {
+        name: num_vals,
+        grammar: "a = 1*%x41-5A %x0D.0A",
+        input: "ABC\r\n",
+        matches: true
+    }
+
+    test! {
+        name: core_rules_available,
+        grammar: "a = 1*HEXDIG",
+        input: "c0ffee",
+        matches: true
+    }
+
+    test! {
+        name: extended_code_points,
+        grammar: "a = %x80-10FFFF",
+        input: "\u{1F600}",
+        matches: true
+    }
+
+    test! {
+        name: trailing_input,
+        grammar: "a = \"x\"",
+        input: "xx",
+        matches: false
+    }
+
+    test! {
+        name: left_recursion,
+        grammar: "a = a \"x\" / \"x\"",
+        input: "xxx",
+        matches: true
+    }
+
+    test! {
+        name: hidden_left_recursion,
+        grammar: "a = [\"y\"] a \"x\" / \"x\"",
+        input: "xxx",
+        matches: true
+    }
+
+    test! {
+        name: case_insensitive_rule_names,
+        grammar: "Key = KEY-char *key-Char\nkey-char = alpha",
+        input: "key",
+        matches: true
+    }
+
+    #[test]
+    fn reports_furthest_position() {
+        let grammar = parse("a = \"ab\" (\"c\" / %x30-39)");
+
+        let err = text_matcher(&grammar, None, "abx")
+            .matches("a")
+            .unwrap_err();
+        assert_eq!(err.get_kind(), ReportKind::InputRejectedError);
+        assert!(err.to_string().contains("expected one of \"c\", %x30-39"));
+    }
+
+    #[test]
+    fn prose_never_matches() {
+        let grammar = parse("a = b / \"x\"\nb = <anything at all>");
+
+        let err = text_matcher(&grammar, None, "y").matches("a").unwrap_err();
+        assert!(err.to_string().contains("depends on prose"));
+    }
+
+    #[test]
+    fn encodings() {
+        let grammar = parse("a = %x00-FF %xFF \"\u{e9}\"");
+        let matches = |input: &[u8], encoding| {
+            Matcher::new(&grammar, None, input, encoding)
+                .and_then(|matcher| matcher.matches("a").map(|_| ()))
+                .is_ok()
+        };
+
+        assert!(matches(&[0x00, 0xFF, 0xC3, 0xA9], Encoding::Bytes));
+        assert!(!matches(&[0x00, 0xFF, 0xE9], Encoding::Bytes));
+        assert!(matches("\u{7f}\u{ff}\u{e9}".as_bytes(), Encoding::Utf8));
+        assert!(!matches(&[0x00, 0xFF, 0xC3, 0xA9], Encoding::Utf8));
+        assert!(matches(
+            &[0x01, 0x00, 0xFF, 0x00, 0xE9, 0x00],
+            Encoding::Utf16
+        ));
+        assert!(matches(
+            &[0xFE, 0xFF, 0x00, 0x01, 0x00, 0xFF, 0x00, 0xE9],
+            Encoding::Utf16
+        ));
+        assert!(!matches(&[0x01, 0x00, 0xFF], Encoding::Utf16));
+    }
+
+    fn ambiguous(text: &str, input: &str) -> bool {
+        let grammar = parse(text);
+        let matcher = text_matcher(&grammar, None, input);
+
+        let forest = matcher.matches("a").expect("input should match");
+        matcher.ambiguity(&forest).is_some()
+    }
+
+    #[test]
+    fn ambiguity() {
+        assert!(ambiguous("a = \"x\" / \"x\"", "x"));
+        assert!(ambiguous("a = *b\nb = \"x\" / \"xx\"", "xx"));
+        assert!(ambiguous("a = *[\"x\"]", "x"));
+        assert!(!ambiguous("a = *b\nb = \"x\" / \"xy\"", "xxy"));
+        assert!(!ambiguous("a = 1*3\"x\" *\"y\"", "xxyy"));
+        assert!(!ambiguous("a = a \"x\" / \"x\"", "xxx"));
+    }
+
+    fn tree(text: &str, input: &str) -> String {
+        let grammar = parse(text);
+        let core_rules = core_rules();
+
+        text_matcher(&grammar, Some(&core_rules), input)
+            .matches("a")
+            .expect("input should match")
+            .tree()
+            .to_string()
+    }
+
+    #[test]
+    fn trees() {
+        assert_eq!(
+            tree(
+                "a = b \"-\" c\nb = 1*DIGIT\nc = d / e\nd = \"x\"\ne = \"y\"",
+                "12-y"
+            ),
+            "a 0..4\n  b 0..2\n    DIGIT 0..1\n    DIGIT 1..2\n  c 3..4\n    e 3..4\n"
+        );
+        assert_eq!(
+            tree("a = a \"x\" / \"x\"", "xxx"),
+            "a 0..3\n  a 0..2\n    a 0..1\n"
+        );
+        assert_eq!(tree("a = a / \"x\"", "x"), "a 0..1\n");
+        assert_eq!(
+            tree("a = *b\nb = \"\u{e9}\"", "\u{e9}\u{e9}"),
+            "a 0..4\n  b 0..2\n  b 2..4\n"
+        );
+    }
+
+    #[test]
+    fn cln_document() {
+        let grammar = parse(include_str!("../cln.abnf"));
+        let core_rules = core_rules();
+        let input = "[server]\n{ host = localhost, port = 'int 8080 }";
+
+        assert!(text_matcher(&grammar, Some(&core_rules), input)
+            .matches("cln")
+            .is_ok());
+    }
+}
@@ -0,0 +1,586 @@
+use crate::expr::{Expr, ExprKind, NumVal, Radix};
+use crate::grammar::{DefinedAs, Grammar, Rule};
+use crate::report::Report;
+use crate::report_kind::ReportKind;
+use crate::span::Span;
+use crate::token::Token;
+use crate::token_kind::TokenKind;
+
+type ParseResult<T> = Result<T, Report>;
+
+pub struct Parser<'t, 's> {
+    src: &'s str,
+    tokens: &'t [Token<'s>],
+    cursor: usize,
+}
+
+impl<'t, 's> Parser<'t, 's> {
+    /// `tokens` is expected to be the output of `Lexer::tokenize`, ending in `EOF`
+    pub fn new(source: &'s str, tokens: &'t [Token<'s>]) -> Self {
+        Parser {
+            src: source,
+            tokens,
+            cursor: 0,
+        }
+    }
+
+    pub fn parse(&mut self) -> Result<Grammar, Vec<Report>> {
+        let mut reports: Vec<Report> = Vec::new();
+        let mut rules: Vec<Rule> = Vec::new();
+
+        loop {
+            self.skip_blank_lines();
+            if self.peek().kind == TokenKind::EOF {
+                break;
+            }
+
+            match self.parse_rule() {
+                Ok(rule) => rules.push(rule),
+                Err(report) => {
+                    reports.push(report);
+                    self.synchronize();
+                }
+            }
+        }
+
+        if !reports.is_empty() {
+            return Err(reports);
+        }
+
+        let comments = self
+            .tokens
+            .iter()
+            .filter(|token| token.kind == TokenKind::Comment)
+            .map(|token| token.span())
+            .collect();
+
+        Ok(Grammar { rules, comments })
+    }
+
+    // rule = rulename defined-as elements
+    fn parse_rule(&mut self) -> ParseResult<Rule> {
+        let name = self.expect(TokenKind::Identifier, ReportKind::ExpectedRuleNameError)?;
+        self.skip_whitespace();
+
+        let defined_as = match self.peek().kind {
+            TokenKind::Equal => DefinedAs::Basic,
+            TokenKind::EqualSlash => DefinedAs::Incremental,
+            _ => return Err(self.report(ReportKind::ExpectedDefinedAsError)),
+        };
+        self.advance();
+
+        let expr = self.parse_alternation()?;
+
+        // a rule ends at a newline that is not followed by whitespace
+        self.skip_whitespace();
+        if !matches!(self.peek().kind, TokenKind::Newline | TokenKind::EOF) {
+            return Err(self.report(ReportKind::UnexpectedTokenError));
+        }
+
+        Ok(Rule {
+            name: name.get_lexeme().to_string(),
+            defined_as,
+            span: name.span().to(expr.span),
+            name_span: name.span(),
+            expr,
+        })
+    }
+
+    // alternation = concatenation *( "/" concatenation )
+    fn parse_alternation(&mut self) -> ParseResult<Expr> {
+        let first = self.parse_concatenation()?;
+        if self.peek_past_whitespace().kind != TokenKind::Slash {
+            return Ok(first);
+        }
+
+        let mut alternatives = vec![first];
+        while self.peek_past_whitespace().kind == TokenKind::Slash {
+            self.skip_whitespace();
+            self.advance();
+            alternatives.push(self.parse_concatenation()?);
+        }
+
+        Ok(Self::collect(ExprKind::Alternation, alternatives))
+    }
+
+    // concatenation = repetition *( 1*c-wsp repetition )
+    fn parse_concatenation(&mut self) -> ParseResult<Expr> {
+        self.skip_whitespace();
+
+        let mut items = Vec::new();
+        loop {
+            items.push(self.parse_repetition()?);
+
+            // `b"c"` is not a concatenation
+            if Self::starts_element(&self.peek().kind) {
+                return Err(self
+                    .report(ReportKind::UnexpectedTokenError)
+                    .help("separate the elements with whitespace"));
+            }
+            if !Self::starts_element(&self.peek_past_whitespace().kind) {
+                break;
+            }
+            self.skip_whitespace();
+        }
+
+        if items.len() == 1 {
+            return Ok(items.remove(0));
+        }
+
+        Ok(Self::collect(ExprKind::Concatenation, items))
+    }
+
+    // repetition = [ repeat ] element
+    fn parse_repetition(&mut self) -> ParseResult<Expr> {
+        let start = self.peek().span();
+
+        let bounds = match self.peek().kind {
+            TokenKind::Repeat { min, max } => {
+                self.advance();
+                Some((min, max))
+            }
+            _ => None,
+        };
+
+        let element = self.parse_element()?;
+
+        match bounds {
+            Some((min, max)) => Ok(Expr::new(
+                ExprKind::Repetition {
+                    min,
+                    max,
+                    expr: Box::new(element.clone()),
+                },
+                start.to(element.span),
+            )),
+            None => Ok(element),
+        }
+    }
+
+    // element = rulename / group / option / char-val / num-val / prose-val
+    fn parse_element(&mut self) -> ParseResult<Expr> {
+        let token = self.peek().clone();
+
+        match token.kind {
+            TokenKind::Identifier => {
+                self.advance();
+                Ok(Expr::new(
+                    ExprKind::Rule(token.get_lexeme().to_string()),
+                    token.span(),
+                ))
+            }
+            TokenKind::String => self.parse_char_val(),
+            TokenKind::Mod => match self.token_at(self.cursor + 1).kind {
+                TokenKind::TerminalSensitive | TokenKind::TerminalInsensitive => {
+                    self.parse_char_val()
+                }
+                _ => self.parse_num_val(),
+            },
+            TokenKind::LeftParen => {
+                let (expr, span) = self.parse_bracketed(TokenKind::RightParen)?;
+                Ok(Expr::new(ExprKind::Group(Box::new(expr)), span))
+            }
+            TokenKind::LeftSquare => {
+                let (expr, span) = self.parse_bracketed(TokenKind::RightSquare)?;
+                Ok(Expr::new(ExprKind::Option(Box::new(expr)), span))
+            }
+            TokenKind::Prose => {
+                self.advance();
+                let lexeme = token.get_lexeme();
+                Ok(Expr::new(
+                    ExprKind::ProseVal(lexeme[1..lexeme.len() - 1].to_string()),
+                    token.span(),
+                ))
+            }
+            _ => Err(self.report(ReportKind::ExpectedElementError)),
+        }
+    }
+
+    fn parse_bracketed(&mut self, close: TokenKind) -> ParseResult<(Expr, Span)> {
+        let open = self.advance().span();
+        let expr = self.parse_alternation()?;
+        self.skip_whitespace();
+        let close = self.expect(close, ReportKind::UnexpectedTokenError)?;
+
+        Ok((expr, open.to(close.span())))
+    }
+
+    // char-val = case-insensitive-string / case-sensitive-string, RFC 7405
+    fn parse_char_val(&mut self) -> ParseResult<Expr> {
+        let start = self.peek().span();
+
+        let mut case_sensitive = false;
+        if self.peek().kind == TokenKind::Mod {
+            self.advance();
+            case_sensitive = self.advance().kind == TokenKind::TerminalSensitive;
+        }
+
+        let token = self.expect(TokenKind::String, ReportKind::ExpectedStringError)?;
+        let lexeme = token.get_lexeme();
+
+        Ok(Expr::new(
+            ExprKind::CharVal {
+                value: lexeme[1..lexeme.len() - 1].to_string(),
+                case_sensitive,
+            },
+            start.to(token.span()),
+        ))
+    }
+
+    // num-val = "%" ( bin-val / dec-val / hex-val )
+    fn parse_num_val(&mut self) -> ParseResult<Expr> {
+        let start = self.advance().span();
+
+        let radix = match self.advance().kind {
+            TokenKind::TerminalBinary => Radix::Binary,
+            TokenKind::TerminalDecimal => Radix::Decimal,
+            TokenKind::TerminalHexadecimal => Radix::Hexadecimal,
+            _ => return Err(self.report(ReportKind::IncorrectTerminalFoundError)),
+        };
+
+        let first = self.parse_terminal_value(&radix)?;
+        let value = if self.peek().kind == TokenKind::Range {
+            self.advance();
+            NumVal::Range(first, self.parse_terminal_value(&radix)?)
+        } else {
+            let mut values = vec![first];
+            while self.peek().kind == TokenKind::Dot {
+                self.advance();
+                values.push(self.parse_terminal_value(&radix)?);
+            }
+            NumVal::Sequence(values)
+        };
+
+        // a value is either a range or a sequence, never both
+        if matches!(self.peek().kind, TokenKind::Range | TokenKind::Dot) {
+            return Err(self.report(ReportKind::MixedNumValError));
+        }
+
+        let end = self.tokens[self.cursor - 1].span();
+        let span = start.to(end);
+        if let NumVal::Range(min, max) = value {
+            if min > max {
+                return Err(Report::from_span(
+                    ReportKind::InvertedRangeError,
+                    self.src,
+                    span,
+                ));
+            }
+        }
+
+        Ok(Expr::new(ExprKind::NumVal { radix, value }, span))
+    }
+
+    fn parse_terminal_value(&mut self, radix: &Radix) -> ParseResult<u32> {
+        if !matches!(
+            self.peek().kind,
+            TokenKind::Binary | TokenKind::Decimal | TokenKind::Hexadecimal
+        ) {
+            return Err(self.report(ReportKind::NaNError));
+        }
+
+        let token = self.advance();
+        u32::from_str_radix(token.get_lexeme(), radix.base())
+            .map_err(|_| Report::from_span(ReportKind::NaNError, self.src, token.span()))
+    }
+
+    fn collect(kind: fn(Vec<Expr>) -> ExprKind, items: Vec<Expr>) -> Expr {
+        let span = items[0].span.to(items[items.len() - 1].span);
+        Expr::new(kind(items), span)
+    }
+
+    fn starts_element(kind: &TokenKind) -> bool {
+        matches!(
+            kind,
+            TokenKind::Identifier
+                | TokenKind::Repeat { .. }
+                | TokenKind::String
+                | TokenKind::Mod
+                | TokenKind::LeftParen
+                | TokenKind::LeftSquare
+                | TokenKind::Prose
+        )
+    }
+
+    /// skip to the end of the rule after an error
+    fn synchronize(&mut self) {
+        while !matches!(self.peek().kind, TokenKind::Newline | TokenKind::EOF) {
+            self.advance();
+        }
+    }
+
+    fn expect(&mut self, kind: TokenKind, report: ReportKind) -> ParseResult<&'t Token<'s>> {
+        if self.peek().kind != kind {
+            return Err(self.report(report));
+        }
+
+        Ok(self.advance())
+    }
+
+    /// skip c-wsp, whitespace, comments and continued lines
+    fn skip_whitespace(&mut self) {
+        self.cursor = self.index_past_whitespace(self.cursor);
+    }
+
+    /// skip empty lines between rules
+    fn skip_blank_lines(&mut self) {
+        while matches!(
+            self.peek().kind,
+            TokenKind::Whitespace
+                | TokenKind::Comment
+                | TokenKind::Continuation
+                | TokenKind::Newline
+        ) {
+            self.advance();
+        }
+    }
+
+    fn index_past_whitespace(&self, mut index: usize) -> usize {
+        while matches!(
+            self.token_at(index).kind,
+            TokenKind::Whitespace | TokenKind::Comment | TokenKind::Continuation
+        ) {
+            index += 1;
+        }
+        index
+    }
+
+    fn peek_past_whitespace(&self) -> &'t Token<'s> {
+        self.token_at(self.index_past_whitespace(self.cursor))
+    }
+
+    fn peek(&self) -> &'t Token<'s> {
+        self.token_at(self.cursor)
+    }
+
+    fn advance(&mut self) -> &'t Token<'s> {
+        let token = self.token_at(self.cursor);
+        if self.cursor < self.tokens.len() - 1 {
+            self.cursor += 1;
+        }
+        token
+    }
+
+    // the last token is always EOF, never read past it
+    fn token_at(&self, index: usize) -> &'t Token<'s> {
+        &self.tokens[index.min(self.tokens.len() - 1)]
+    }
+
+    fn report(&self, kind: ReportKind) -> Report {
+        Report::from_span(kind, self.src, self.peek().span())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::LexerConfig;
+    use crate::encoding::Encoding;
+    use crate::lexer::Lexer;
+
+    macro_rules! test {
+        {
+            name:   $name:ident,
+            text:   $text:expr,
+            rules:  ($($rule:expr),*)
+        } => {
+            #[test]
+            fn $name() {
+                let rules: &[&str] = &[$($rule,)*];
+
+                test($text, Some(rules), None);
+            }
+        };
+    }
+
+    macro_rules! error {
+        {
+            name:   $name:ident,
+            text:   $text:expr,
+            errors: ($($kind:expr),*)
+        } => {
+            #[test]
+            fn $name() {
+                let kinds: &[ReportKind] = &[$($kind,)*];
+
+                test($text, None, Some(kinds));
+            }
+        }
+    }
+
+    fn parse(text: &str) -> Result<Grammar, Vec<Report>> {
+        let config = LexerConfig { extended: true };
+        let (tokens, _) = Lexer::new(text, config, Encoding::default())
+            .tokenize()
+            .expect("test grammar should lex");
+
+        Parser::new(text, &tokens).parse()
+    }
+
+    fn test(text: &str, want_rules: Option<&[&str]>, want_report_kinds: Option<&[ReportKind]>) {
+        match parse(text) {
+            Ok(grammar) => {
+                let have_rules = grammar
+                    .rules
+                    .iter()
+                    .map(|r| r.to_string())
+                    .collect::<Vec<String>>();
+
+                assert_eq!(have_rules, want_rules.expect("test produces no errors"));
+            }
+            Err(err) => {
+                let Some(want_report_kinds) = want_report_kinds else {
+                    for e in err {
+                        println!("{e}");
+                    }
+                    panic!("== test produces errors, run test command with --nocapture to see output ==")
+                };
+
+                let have_report_kinds = err
+                    .iter()
+                    .map(|r| r.get_kind())
+                    .collect::<Vec<ReportKind>>();
+
+                assert_eq!(have_report_kinds, want_report_kinds);
+            }
+        }
+    }
+
+    test! {
+        name: rule_single_element,
+        text: "a = b",
+        rules: ("a = b")
+    }
+
+    test! {
+        name: rule_incremental,
+        text: "a = b\na =/ c",
+        rules: ("a = b", "a =/ c")
+    }
+
+    test! {
+        name: rules_split_without_blank_line,
+        text: "a = b c\nd = e",
+        rules: ("a = b c", "d = e")
+    }
+
+    test! {
+        name: alternation_and_concatenation,
+        text: "a = b c / d   /e",
+        rules: ("a = b c / d / e")
+    }
+
+    test! {
+        name: case_sensitive_strings,
+        text: "a = %s\"aB\" / %i\"aB\" / \"aB\"",
+        rules: ("a = %s\"aB\" / \"aB\" / \"aB\"")
+    }
+
+    test! {
+        name: repetitions,
+        text: "a = *b 1*c *2d 3*4e 5f",
+        rules: ("a = *b 1*c *2d 3*4e 5f")
+    }
+
+    test! {
+        name: group_and_option,
+        text: "a = (b / c) [d e]",
+        rules: ("a = ( b / c ) [ d e ]")
+    }
+
+    test! {
+        name: char_val,
+        text: "a = \"hello\" b",
+        rules: ("a = \"hello\" b")
+    }
+
+    test! {
+        name: num_vals,
+        text: "a = %x41-5A / %x0D.0A / %d13 / %b1100110",
+        rules: ("a = %x41-5A / %x0D.0A / %d13 / %b1100110")
+    }
+
+    test! {
+        name: prose_val,
+        text: "a = <some prose>",
+        rules: ("a = <some prose>")
+    }
+
+    test! {
+        name: rule_continued_on_indented_line,
+        text: "a = b\n  / c\n\n  \nd =\n e",
+        rules: ("a = b / c", "d = e")
+    }
+
+    test! {
+        name: continuation_lines_and_comments,
+        text: "ws = *(\n  %x20 / ; Space\n  %x09\n  )\nnl = %x0A",
+        rules: ("ws = *( %x20 / %x09 )", "nl = %x0A")
+    }
+
+    #[test]
+    fn spans_cover_source() {
+        let text = "key = quoted / 1*unquoted";
+        let grammar = parse(text).unwrap();
+        let rule = &grammar.rules[0];
+
+        assert_eq!(&text[rule.name_span.start..rule.name_span.end], "key");
+        assert_eq!(&text[rule.span.start..rule.span.end], text);
+
+        let ExprKind::Alternation(alternatives) = &rule.expr.kind else {
+            panic!("expected alternation, found {:?}", rule.expr.kind);
+        };
+        let span = alternatives[1].span;
+        assert_eq!(&text[span.start..span.end], "1*unquoted");
+    }
+
+    #[test]
+    fn parses_cln() {
+        let source = include_str!("../cln.abnf");
+        let grammar = parse(source).unwrap();
+
+        assert_eq!(grammar.rules.len(), 67);
+    }
+
+    error! {
+        name: missing_rule_name,
+        text: "= b",
+        errors: (ReportKind::ExpectedRuleNameError)
+    }
+
+    error! {
+        name: missing_defined_as,
+        text: "a b",
+        errors: (ReportKind::ExpectedDefinedAsError)
+    }
+
+    error! {
+        name: missing_element,
+        text: "a = b /\nc = ()",
+        errors: (ReportKind::ExpectedElementError, ReportKind::ExpectedElementError)
+    }
+
+    error! {
+        name: unseparated_elements,
+        text: "a = b\"c\"(b)[b]\nb = (c d)e",
+        errors: (ReportKind::UnexpectedTokenError, ReportKind::UnexpectedTokenError)
+    }
+
+    error! {
+        name: unindented_line_ends_rule,
+        text: "a = b\n/ c\nd = e",
+        errors: (ReportKind::ExpectedRuleNameError)
+    }
+
+    error! {
+        name: mixed_num_val,
+        text: "a = %x41-42.43\nb = %x41.42-43",
+        errors: (ReportKind::MixedNumValError, ReportKind::MixedNumValError)
+    }
+
+    error! {
+        name: inverted_range,
+        text: "a = %x5A-41",
+        errors: (ReportKind::InvertedRangeError)
+    }
+}
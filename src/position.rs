@@ -13,4 +13,27 @@ impl Position {
             offset: 0,
         }
     }
+
+    /// compute line and column of a byte offset the same way the lexer does,
+    /// columns count chars
+    pub fn from_offset(src: &str, offset: usize) -> Self {
+        let before = &src[..offset];
+        let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+
+        Position {
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+            offset,
+        }
+    }
+
+    /// the source line this position is on, without its line terminator
+    pub fn line<'s>(&self, src: &'s str) -> &'s str {
+        let start = src[..self.offset].rfind('\n').map_or(0, |index| index + 1);
+        let end = src[self.offset..]
+            .find('\n')
+            .map_or(src.len(), |index| index + self.offset);
+
+        src[start..end].trim_end_matches('\r')
+    }
 }
@@ -1,55 +1,291 @@
+use crate::fix::Edit;
 use crate::position::Position;
 use crate::report_kind::ReportKind;
+use crate::severity::Severity;
+use crate::span::Span;
 
 use owo_colors::OwoColorize;
 
+// tabs are shown as spaces up to the next multiple of this
+const TAB_WIDTH: usize = 4;
+
 #[derive(Debug)]
 pub struct Report {
     kind: ReportKind,
-    msg: &'static str,
+    severity: Severity,
 
     pos: Option<Position>,
+    // length in bytes of what the report is about, starting at `pos`
+    len: usize,
+    line: Box<str>,
+
+    labels: Vec<Label>,
+    // notes and help, in the order they are rendered
+    notes: Vec<(Severity, String)>,
+    // machine-applicable fix, see `fix`, boxed as few reports have one
+    fix: Option<Box<Edit>>,
+}
+
+/// a secondary span of a report with a message, e.g. where a bracket was opened
+#[derive(Debug)]
+struct Label {
+    pos: Position,
+    len: usize,
     line: String,
+    msg: String,
 }
 
 impl Report {
     pub fn new(kind: ReportKind, pos: Option<Position>, line: String) -> Self {
-        let msg = kind.msg();
-
         Report {
+            severity: kind.severity(),
             kind,
-            msg,
             pos,
-            line,
+            len: 0,
+            line: line.into_boxed_str(),
+            labels: Vec::new(),
+            notes: Vec::new(),
+            fix: None,
         }
     }
 
+    /// build a report about `span` in `src`
+    pub fn from_span(kind: ReportKind, src: &str, span: Span) -> Self {
+        let pos = Position::from_offset(src, span.start);
+        let line = pos.line(src).to_string();
+
+        Report {
+            len: span.end - span.start,
+            ..Report::new(kind, Some(pos), line)
+        }
+    }
+
+    /// attach extra context, rendered below the report
+    pub fn note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push((Severity::Note, note.into()));
+        self
+    }
+
+    /// suggest how to fix the report, rendered below the report
+    pub fn help(mut self, help: impl Into<String>) -> Self {
+        self.notes.push((Severity::Help, help.into()));
+        self
+    }
+
+    /// replace `span` of the source with `replacement` to fix the report,
+    /// only for fixes that are always right
+    pub fn fix(mut self, span: Span, replacement: impl Into<String>) -> Self {
+        self.fix = Some(Box::new(Edit {
+            span,
+            replacement: replacement.into(),
+        }));
+        self
+    }
+
+    /// point at another span of `src` that explains the report
+    pub fn label(mut self, src: &str, span: Span, msg: impl Into<String>) -> Self {
+        let pos = Position::from_offset(src, span.start);
+
+        self.labels.push(Label {
+            line: pos.line(src).to_string(),
+            pos,
+            len: span.end - span.start,
+            msg: msg.into(),
+        });
+        self
+    }
+
     pub fn get_kind(&self) -> ReportKind {
         self.kind.clone()
     }
+
+    pub fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    pub fn set_severity(&mut self, severity: Severity) {
+        self.severity = severity;
+    }
+
+    pub fn msg(&self) -> &'static str {
+        self.kind.msg()
+    }
+
+    pub fn pos(&self) -> Option<&Position> {
+        self.pos.as_ref()
+    }
+
+    /// byte range the report is about, empty when only a position is known
+    pub fn span(&self) -> Option<Span> {
+        self.pos
+            .as_ref()
+            .map(|pos| Span::new(pos.offset, pos.offset + self.len))
+    }
+
+    pub fn edit(&self) -> Option<&Edit> {
+        self.fix.as_deref()
+    }
+
+    pub fn notes(&self) -> Vec<&str> {
+        self.notes_of(Severity::Note)
+    }
+
+    pub fn helps(&self) -> Vec<&str> {
+        self.notes_of(Severity::Help)
+    }
+
+    fn notes_of(&self, severity: Severity) -> Vec<&str> {
+        self.notes
+            .iter()
+            .filter(|(of, _)| *of == severity)
+            .map(|(_, note)| note.as_str())
+            .collect()
+    }
+
+    // capitalised severity in its color
+    fn severity_label(&self) -> String {
+        let label = match self.severity {
+            Severity::Error => "Error",
+            Severity::Warning => "Warning",
+            Severity::Note => "Note",
+            Severity::Help => "Help",
+        };
+
+        match self.severity {
+            Severity::Error => label.red().to_string(),
+            Severity::Warning => label.yellow().to_string(),
+            Severity::Note => label.blue().to_string(),
+            Severity::Help => label.cyan().to_string(),
+        }
+    }
 }
 
 impl std::fmt::Display for Report {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match &self.pos {
             Some(pos) => {
-                let col = pos.column;
-
                 write!(
                     f,
-                    "{} -- on Line {} at char {}: {} -- {}\n\
-                    {}\n\
-                    {:>col$}",
-                    "Error".red(),
+                    "{}[{}] -- on Line {} at char {}: {} -- {}\n{}\n{}",
+                    self.severity_label(),
+                    self.kind.code(),
                     pos.line.green(),
                     pos.column.green(),
                     self.kind.red(),
-                    self.msg,
-                    self.line,
-                    "^".bold().green(),
-                )
+                    self.kind.msg(),
+                    expand_tabs(&self.line),
+                    underline(&self.line, pos.column, self.len, '^')
+                        .bold()
+                        .green(),
+                )?;
+
+                for label in &self.labels {
+                    if label.pos.line != pos.line {
+                        write!(f, "\n{}", expand_tabs(&label.line))?;
+                    }
+
+                    let underline = underline(&label.line, label.pos.column, label.len, '-');
+                    write!(f, "\n{} {}", underline.bold().blue(), label.msg)?;
+                }
             }
-            None => write!(f, "ERROR -- {} -- {}\n", self.kind, self.msg),
+            None => write!(
+                f,
+                "{}[{}] -- {} -- {}",
+                self.severity.to_string().to_uppercase(),
+                self.kind.code(),
+                self.kind,
+                self.kind.msg()
+            )?,
+        }
+
+        for (severity, note) in &self.notes {
+            write!(f, "\n{} {}", format!("= {severity}:").bold(), note)?;
         }
+
+        Ok(())
+    }
+}
+
+fn expand_tabs(line: &str) -> String {
+    let mut expanded = String::new();
+    for c in line.chars() {
+        match c {
+            '\t' => {
+                let width = TAB_WIDTH - expanded.chars().count() % TAB_WIDTH;
+                expanded.extend(std::iter::repeat_n(' ', width));
+            }
+            _ => expanded.push(c),
+        }
+    }
+
+    expanded
+}
+
+// `marker` under `len` bytes of `line` from the char at `column`, at least one
+// so empty spans are still visible
+fn underline(line: &str, column: usize, len: usize, marker: char) -> String {
+    let start = line
+        .char_indices()
+        .nth(column - 1)
+        .map_or(line.len(), |(index, _)| index);
+    let end = (start + len).min(line.len());
+
+    let before = expand_tabs(&line[..start]).chars().count();
+    let width = expand_tabs(&line[..end]).chars().count() - before;
+
+    format!(
+        "{}{}",
+        " ".repeat(before),
+        marker.to_string().repeat(width.max(1))
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the rendered report without colors
+    fn render(report: &Report) -> String {
+        let mut plain = String::new();
+        let mut chars = report.to_string().chars().collect::<Vec<_>>().into_iter();
+        while let Some(c) = chars.next() {
+            match c {
+                '\x1b' => while chars.next().is_some_and(|c| c != 'm') {},
+                _ => plain.push(c),
+            }
+        }
+
+        plain
+    }
+
+    #[test]
+    fn underlines_span() {
+        let src = "a = b / c\n";
+        let report = Report::from_span(ReportKind::UndefinedRuleError, src, Span::new(4, 9));
+
+        assert!(render(&report).ends_with("a = b / c\n    ^^^^^"));
+    }
+
+    #[test]
+    fn display_columns() {
+        let src = "\ta = \"\u{e9}\" @";
+        let report =
+            Report::from_span(ReportKind::UnexpectedCharacterError, src, Span::new(10, 11));
+
+        assert_eq!(report.pos().unwrap().column, 10);
+        assert!(render(&report).ends_with("    a = \"\u{e9}\" @\n            ^"));
+    }
+
+    #[test]
+    fn labels() {
+        let src = "a = (b\n  / c]\n";
+        let report = Report::from_span(
+            ReportKind::MismatchedClosingBracketError,
+            src,
+            Span::new(12, 13),
+        )
+        .label(src, Span::new(4, 5), "bracket opened here");
+
+        assert!(render(&report).ends_with("  / c]\n     ^\na = (b\n    - bracket opened here"));
     }
 }
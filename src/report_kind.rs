@@ -1,8 +1,11 @@
-use strum_macros::Display;
+use crate::severity::Severity;
+use serde::Deserialize;
+use strum::IntoEnumIterator;
+use strum_macros::{Display, EnumIter};
 
-#[derive(Display, Debug, PartialEq, Clone)]
+#[derive(Display, EnumIter, Deserialize, Debug, PartialEq, Eq, Hash, Clone)]
 pub enum ReportKind {
-    UnableToParseError,
+    UnexpectedCharacterError,
     UnableToAdvanceError,
     InternalLexerError,
     EofError,
@@ -20,33 +23,191 @@ pub enum ReportKind {
     DecimalTerminalError,
     HexadecimalTerminalError,
     NaHexNError,
+    TerminalOverflowError,
+    SurrogateTerminalError,
+    TerminalRangeError,
+    ExpectedStringError,
 
     // literal errors
     UnterminatedStringError,
+    StringCharacterError,
+    UnterminatedProseError,
+    ProseCharacterError,
     NaNError,
+
+    // repeat errors
+    RepeatWhitespaceError,
+    InvertedRepeatError,
+
+    // parser errors
+    ExpectedRuleNameError,
+    ExpectedDefinedAsError,
+    ExpectedElementError,
+    UnexpectedTokenError,
+    MixedNumValError,
+    InvertedRangeError,
+
+    // grammar errors
+    UndefinedRuleError,
+    DuplicateRuleError,
+    IncrementalBeforeDefinitionError,
+    UnreachableRuleWarning,
+    CoreRuleRedefinitionWarning,
+    ProseValWarning,
+    InconsistentCasingWarning,
+    BroadRangeWarning,
+    RedundantGroupWarning,
+    EmptyAlternativeWarning,
+    LowercaseHexWarning,
+    CoreRuleValueWarning,
+    UnknownLintWarning,
+
+    // matcher errors
+    UnknownRuleError,
+    InputRejectedError,
+    InputEncodingError,
+    AmbiguousInputWarning,
+
+    // generator errors
+    UngeneratableRuleError,
 }
 
 impl ReportKind {
+    /// stable code of the report, new kinds get the next free code and codes
+    /// of removed kinds are never reused
+    pub fn code(&self) -> &'static str {
+        use ReportKind::*;
+
+        match &self {
+            UnexpectedCharacterError => "A0001",
+            UnableToAdvanceError => "A0002",
+            InternalLexerError => "A0003",
+            EofError => "A0004",
+            UnclosedBracketError => "A0005",
+            MismatchedClosingBracketError => "A0006",
+            UnexpectedClosingBracketError => "A0007",
+            NoTerminalFoundError => "A0008",
+            IncorrectTerminalFoundError => "A0009",
+            BinaryTerminalError => "A0010",
+            SevenBitsError => "A0011",
+            DecimalTerminalError => "A0012",
+            HexadecimalTerminalError => "A0013",
+            NaHexNError => "A0014",
+            TerminalOverflowError => "A0015",
+            SurrogateTerminalError => "A0016",
+            TerminalRangeError => "A0017",
+            ExpectedStringError => "A0018",
+            UnterminatedStringError => "A0019",
+            StringCharacterError => "A0020",
+            UnterminatedProseError => "A0021",
+            ProseCharacterError => "A0022",
+            NaNError => "A0023",
+            RepeatWhitespaceError => "A0024",
+            InvertedRepeatError => "A0025",
+            ExpectedRuleNameError => "A0026",
+            ExpectedDefinedAsError => "A0027",
+            ExpectedElementError => "A0028",
+            UnexpectedTokenError => "A0029",
+            MixedNumValError => "A0030",
+            InvertedRangeError => "A0031",
+            UndefinedRuleError => "A0032",
+            DuplicateRuleError => "A0033",
+            IncrementalBeforeDefinitionError => "A0034",
+            UnreachableRuleWarning => "A0035",
+            CoreRuleRedefinitionWarning => "A0036",
+            ProseValWarning => "A0037",
+            UnknownRuleError => "A0038",
+            InputRejectedError => "A0039",
+            InputEncodingError => "A0040",
+            AmbiguousInputWarning => "A0041",
+            UngeneratableRuleError => "A0042",
+            InconsistentCasingWarning => "A0043",
+            BroadRangeWarning => "A0044",
+            RedundantGroupWarning => "A0045",
+            EmptyAlternativeWarning => "A0046",
+            LowercaseHexWarning => "A0047",
+            CoreRuleValueWarning => "A0048",
+            UnknownLintWarning => "A0049",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        ReportKind::iter().find(|kind| kind.code().eq_ignore_ascii_case(code))
+    }
+
+    /// severity of the report unless the config overrides it
+    pub fn severity(&self) -> Severity {
+        use ReportKind::*;
+
+        match self {
+            UnreachableRuleWarning
+            | CoreRuleRedefinitionWarning
+            | ProseValWarning
+            | InconsistentCasingWarning
+            | BroadRangeWarning
+            | RedundantGroupWarning
+            | EmptyAlternativeWarning
+            | LowercaseHexWarning
+            | CoreRuleValueWarning
+            | UnknownLintWarning
+            | AmbiguousInputWarning => Severity::Warning,
+            _ => Severity::Error,
+        }
+    }
+
     pub fn msg(&self) -> &'static str {
         use ReportKind::*;
 
         match &self {
-            UnableToParseError => "lexer was unable to tokenize file",
+            UnexpectedCharacterError => "character can not start any ABNF token",
             UnableToAdvanceError => "Unable To advance tokenstream",
             InternalLexerError => "you should never see this error",
             EofError => "reached end of file before complete expression",
-            MismatchedClosingBracketError => "one or more brackets are never closed",
+            MismatchedClosingBracketError => "closing bracket does not match the opening bracket",
             UnexpectedClosingBracketError => "unexpected closing bracket",
             UnclosedBracketError => "one or more brackets are never closed",
-            NoTerminalFoundError => "expected terminal (b, d, x) after %, found none",
-            IncorrectTerminalFoundError => "expected terminal (b, d, x) after %",
+            NoTerminalFoundError => "expected terminal (b, d, x, s, i) after %, found none",
+            IncorrectTerminalFoundError => "expected terminal (b, d, x, s, i) after %",
             BinaryTerminalError => "expected binary representation (0, 1)",
             SevenBitsError => "expected 7 bits after terminal received more or less",
             DecimalTerminalError => "decimal terminal should be in range 0..=127",
             HexadecimalTerminalError => "hexadecimal terminal should be in range 00..=7E",
-            UnterminatedStringError => "string was never closed",
+            UnterminatedStringError => "string was never closed on the same line",
+            StringCharacterError => "strings may only contain printable characters other than \"",
+            UnterminatedProseError => "prose was never closed with > on the same line",
+            ProseCharacterError => "prose may only contain printable characters other than >",
             NaNError => "expected a number",
             NaHexNError => "expected a 2 character hexadecimal",
+            TerminalOverflowError => "terminal value does not fit in 32 bits",
+            SurrogateTerminalError => "terminal value is a surrogate (D800-DFFF), not a character",
+            TerminalRangeError => "terminal value is too large for the grammar's encoding",
+            ExpectedStringError => "expected a quoted string after %s or %i",
+            RepeatWhitespaceError => "whitespace is not allowed in or after a repeat prefix",
+            InvertedRepeatError => "minimum repetitions is greater than the maximum",
+            ExpectedRuleNameError => "expected a rule name at the start of a rule",
+            ExpectedDefinedAsError => "expected = or =/ after rule name",
+            ExpectedElementError => "expected an element (rule name, group, option or value)",
+            UnexpectedTokenError => "unexpected token, expected the end of the rule",
+            MixedNumValError => "a value can be a range (-) or a concatenation (.), not both",
+            InvertedRangeError => "lower bound of the range is greater than its upper bound",
+            UndefinedRuleError => "rule is referenced but never defined",
+            DuplicateRuleError => "rule is already defined, use =/ to add alternatives",
+            IncrementalBeforeDefinitionError => "=/ used before the rule is defined with =",
+            UnreachableRuleWarning => "rule is never reachable from the first rule",
+            CoreRuleRedefinitionWarning => "rule redefines an RFC 5234 Appendix B core rule",
+            ProseValWarning => "prose can not be tested or generated automatically",
+            InconsistentCasingWarning => "rule name is spelled with a different casing",
+            BroadRangeWarning => "range includes every control character",
+            RedundantGroupWarning => "parentheses do not change the meaning",
+            EmptyAlternativeWarning => "alternative only matches the empty string",
+            LowercaseHexWarning => "hexadecimal value is written in lowercase",
+            CoreRuleValueWarning => "value is the same as a core rule",
+            UnknownLintWarning => "comment allows a lint that does not exist",
+            UnknownRuleError => "rule is not defined in the grammar",
+            InputRejectedError => "input does not match the rule",
+            InputEncodingError => "input is not valid in the grammar's encoding",
+            AmbiguousInputWarning => "input has more than one derivation",
+            UngeneratableRuleError => "rule can not be expanded into a finite string",
         }
     }
 }
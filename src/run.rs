@@ -1,34 +1,370 @@
-use crate::cli;
-use crate::config::load_config;
+use crate::check::Checker;
+use crate::cli::{self, Command, Format};
+use crate::config::{load_config, Config, LexerConfig};
+use crate::core_rules::core_rules;
+use crate::emitter::Emitter;
+use crate::encoding::Encoding;
+use crate::explain::explain;
+use crate::fix;
+use crate::generator::{Generator, GeneratorConfig};
+use crate::grammar::Grammar;
 use crate::lexer::Lexer;
-use clap::Parser;
+use crate::lint::Linter;
+use crate::matcher::Matcher;
+use crate::parser::Parser;
+use crate::report::Report;
+use crate::report_kind::ReportKind;
+use clap::Parser as _;
 use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
 
 /// RUN
-pub fn run() {
+pub fn run() -> ExitCode {
     let cli = cli::Cli::parse();
 
-    let config = load_config(cli.config);
-    if let Some(file_path) = cli.file {
-        match fs::read_to_string(file_path) {
-            Ok(source) => {
-                let mut lexer = Lexer::new(&source, config.lexer);
-
-                match lexer.tokenize() {
-                    Ok(tokens) => {
-                        for t in tokens {
-                            println!("{t}");
-                        }
-                    }
-                    Err(err) => {
-                        println!("-The following syntax errors where found:-");
-                        for e in err {
-                            println!("{e}");
-                        }
+    let mut config = load_config(cli.config);
+    let mut emitter = Emitter::new(cli.format, std::mem::take(&mut config.severity));
+    // json output of `test` is the tree, diagnostics go to stderr instead
+    let tree_on_stdout =
+        cli.format == Format::Json && matches!(cli.command, Some(Command::Test { .. }));
+
+    let code = match cli.command {
+        Some(Command::Test {
+            grammar,
+            rule,
+            input,
+            tree,
+        }) => test(
+            config,
+            &mut emitter,
+            &grammar,
+            &rule,
+            &input,
+            tree,
+            cli.format,
+        ),
+        Some(Command::Generate {
+            grammar,
+            rule,
+            seed,
+            count,
+            max_depth,
+            max_length,
+            weight,
+            output,
+        }) => {
+            let seed = seed.unwrap_or_else(|| {
+                let seed = rand::random();
+                eprintln!("using seed {seed}");
+                seed
+            });
+
+            let generator_config = GeneratorConfig {
+                seed,
+                max_depth,
+                max_length,
+                weights: weight.into_iter().collect(),
+                encoding: config.grammar.encoding,
+            };
+
+            generate(
+                config,
+                &mut emitter,
+                &grammar,
+                &rule,
+                count,
+                generator_config,
+                output,
+            )
+        }
+        Some(Command::Explain { code }) => match ReportKind::from_code(&code) {
+            Some(kind) => {
+                println!("{}: {kind} -- {}\n", kind.code(), kind.msg());
+                print!("{}", explain(&kind));
+                ExitCode::SUCCESS
+            }
+            None => {
+                eprintln!("no diagnostic has the code `{code}`");
+                ExitCode::FAILURE
+            }
+        },
+        None => match cli.file {
+            Some(file_path) => check(config, &mut emitter, &file_path, cli.fix, cli.dry_run),
+            None => ExitCode::SUCCESS,
+        },
+    };
+
+    let finished = match tree_on_stdout {
+        true => emitter.finish(&mut std::io::stderr()),
+        false => emitter.finish(&mut std::io::stdout()),
+    };
+    if let Err(err) = finished {
+        eprintln!("{err}");
+        return ExitCode::FAILURE;
+    }
+
+    code
+}
+
+fn check(
+    config: Config,
+    emitter: &mut Emitter,
+    file_path: &Path,
+    apply_fixes: bool,
+    dry_run: bool,
+) -> ExitCode {
+    let Some(source) = read(file_path) else {
+        return ExitCode::FAILURE;
+    };
+
+    let code = check_source(config, emitter, file_path, &source);
+    if !apply_fixes {
+        return code;
+    }
+
+    // the reports are about the file as it was before fixing it
+    let (fixed, applied) = fix::apply(&source, emitter.edits());
+    if dry_run {
+        let diff = fix::diff(&file_path.display().to_string(), &source, &fixed);
+        match emitter.is_human() {
+            true => print!("{diff}"),
+            false => eprint!("{diff}"),
+        }
+    } else if applied > 0 {
+        if let Err(err) = fs::write(file_path, fixed) {
+            eprintln!("{}: {err}", file_path.display());
+            return ExitCode::FAILURE;
+        }
+        let fixes = if applied == 1 { "fix" } else { "fixes" };
+        eprintln!("applied {applied} {fixes} to {}", file_path.display());
+    }
+
+    code
+}
+
+fn check_source(config: Config, emitter: &mut Emitter, file_path: &Path, source: &str) -> ExitCode {
+    let grammar = match parse(
+        emitter,
+        file_path,
+        source,
+        config.lexer,
+        config.grammar.encoding,
+    ) {
+        Some(grammar) => grammar,
+        None => return ExitCode::FAILURE,
+    };
+
+    let core_rules = config.grammar.core_rules.then(core_rules);
+    let reports = diagnose(&config, source, &grammar, core_rules.as_ref());
+    emitter.reports(file_path, reports);
+    if emitter.has_errors() {
+        return ExitCode::FAILURE;
+    }
+
+    if emitter.is_human() {
+        print!("{grammar}");
+    }
+    ExitCode::SUCCESS
+}
+
+fn test(
+    config: Config,
+    emitter: &mut Emitter,
+    grammar_path: &Path,
+    rule: &str,
+    input_path: &Path,
+    tree: bool,
+    format: Format,
+) -> ExitCode {
+    let (Some(source), Some(input)) = (read(grammar_path), read_bytes(input_path)) else {
+        return ExitCode::FAILURE;
+    };
+
+    let grammar = match parse(
+        emitter,
+        grammar_path,
+        &source,
+        config.lexer,
+        config.grammar.encoding,
+    ) {
+        Some(grammar) => grammar,
+        None => return ExitCode::FAILURE,
+    };
+
+    // errors in the grammar fail the test, but the input is still matched
+    let core_rules = config.grammar.core_rules.then(core_rules);
+    let reports = diagnose(&config, &source, &grammar, core_rules.as_ref());
+    emitter.reports(grammar_path, reports);
+
+    let encoding = config.grammar.encoding;
+    let matcher = match Matcher::new(&grammar, core_rules.as_ref(), &input, encoding) {
+        Ok(matcher) => matcher,
+        Err(report) => return fail(emitter, input_path, vec![report]),
+    };
+
+    match matcher.matches(rule) {
+        Ok(forest) => {
+            if let Some(report) = matcher.ambiguity(&forest) {
+                emitter.report(input_path, report);
+            }
+
+            match format {
+                Format::Human => {
+                    println!("input matches rule {rule}");
+                    if tree {
+                        print!("{}", forest.tree());
                     }
                 }
+                Format::Json => {
+                    let json = serde_json::to_string_pretty(&forest.tree())
+                        .expect("trees always serialize");
+                    println!("{json}");
+                }
+                Format::Sarif => {}
+            }
+
+            match emitter.has_errors() {
+                true => ExitCode::FAILURE,
+                false => ExitCode::SUCCESS,
+            }
+        }
+        Err(report) => fail(emitter, input_path, vec![report]),
+    }
+}
+
+fn generate(
+    config: Config,
+    emitter: &mut Emitter,
+    grammar_path: &Path,
+    rule: &str,
+    count: usize,
+    generator_config: GeneratorConfig,
+    output: Option<PathBuf>,
+) -> ExitCode {
+    let Some(source) = read(grammar_path) else {
+        return ExitCode::FAILURE;
+    };
+
+    let grammar = match parse(
+        emitter,
+        grammar_path,
+        &source,
+        config.lexer,
+        config.grammar.encoding,
+    ) {
+        Some(grammar) => grammar,
+        None => return ExitCode::FAILURE,
+    };
+
+    let core_rules = config.grammar.core_rules.then(core_rules);
+    let mut generator = Generator::new(&grammar, core_rules.as_ref(), generator_config);
+
+    for i in 0..count {
+        let generated = match generator.generate(rule) {
+            Ok(generated) => generated,
+            Err(report) => {
+                emitter.report(grammar_path, report);
+                return ExitCode::FAILURE;
+            }
+        };
+
+        // output may be binary, so it is written out as is
+        match &output {
+            Some(dir) => {
+                let path = dir.join(format!("{rule}-{i}.txt"));
+                if let Err(err) = fs::write(&path, generated) {
+                    eprintln!("{}: {err}", path.display());
+                    return ExitCode::FAILURE;
+                }
+            }
+            None => {
+                let mut stdout = std::io::stdout().lock();
+                if let Err(err) = stdout.write_all(&generated).and(stdout.write_all(b"\n")) {
+                    eprintln!("{err}");
+                    return ExitCode::FAILURE;
+                }
             }
-            Err(_err) => println!("Errors"),
         }
     }
+
+    ExitCode::SUCCESS
+}
+
+// semantic reports and lints of a parsed grammar
+fn diagnose(
+    config: &Config,
+    source: &str,
+    grammar: &Grammar,
+    core_rules: Option<&Grammar>,
+) -> Vec<Report> {
+    let mut reports = Checker::new(source, grammar, core_rules).check();
+    reports.extend(
+        Linter::new(
+            source,
+            grammar,
+            core_rules,
+            config.grammar.rule_case,
+            config.lint.clone(),
+        )
+        .lint(),
+    );
+
+    reports
+}
+
+// emits the reports of lexing and parsing, the grammar is only returned when
+// it could be parsed and none of them are errors
+fn parse(
+    emitter: &mut Emitter,
+    path: &Path,
+    source: &str,
+    config: LexerConfig,
+    encoding: Encoding,
+) -> Option<Grammar> {
+    let (tokens, mut reports) = match Lexer::new(source, config, encoding).tokenize() {
+        Ok(lexed) => lexed,
+        Err(err) => {
+            emitter.reports(path, err);
+            return None;
+        }
+    };
+
+    let grammar = match Parser::new(source, &tokens).parse() {
+        Ok(grammar) => Some(grammar),
+        Err(err) => {
+            reports.extend(err);
+            None
+        }
+    };
+    emitter.reports(path, reports);
+
+    grammar.filter(|_| !emitter.has_errors())
+}
+
+fn read(path: &Path) -> Option<String> {
+    match fs::read_to_string(path) {
+        Ok(source) => Some(source),
+        Err(err) => {
+            eprintln!("{}: {err}", path.display());
+            None
+        }
+    }
+}
+
+fn read_bytes(path: &Path) -> Option<Vec<u8>> {
+    match fs::read(path) {
+        Ok(input) => Some(input),
+        Err(err) => {
+            eprintln!("{}: {err}", path.display());
+            None
+        }
+    }
+}
+
+fn fail(emitter: &mut Emitter, path: &Path, reports: Vec<Report>) -> ExitCode {
+    emitter.reports(path, reports);
+
+    ExitCode::FAILURE
 }
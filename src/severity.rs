@@ -0,0 +1,13 @@
+use serde::Deserialize;
+use strum_macros::Display;
+
+/// how much a report matters, only errors fail a grammar
+#[derive(Deserialize, Display, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum Severity {
+    Help,
+    Note,
+    Warning,
+    Error,
+}
@@ -1,4 +1,5 @@
 use crate::position::Position;
+use crate::span::Span;
 use crate::token_kind::TokenKind;
 
 #[derive(Clone, Debug, PartialEq)]
@@ -13,6 +14,10 @@ impl<'s> Token<'s> {
     pub fn get_lexeme(&self) -> &'s str {
         &self.src[self.pos.offset..self.pos.offset + self.length]
     }
+
+    pub fn span(&self) -> Span {
+        Span::new(self.pos.offset, self.pos.offset + self.length)
+    }
 }
 
 impl std::fmt::Display for Token<'_> {
@@ -4,7 +4,6 @@ use strum_macros::Display;
 pub enum TokenKind {
     Equal,
     Dot,
-    Star,
     Mod,
     Range,
     Slash,
@@ -15,13 +14,16 @@ pub enum TokenKind {
     Square,
     LeftSquare,
     RightSquare,
-    Angle,
-    LeftAngle,
-    RightAngle,
 
     // two character tokens
     EqualSlash,
 
+    // repeat = 1*DIGIT / (*DIGIT "*" *DIGIT), `*` alone is min 0 and no max
+    Repeat {
+        min: usize,
+        max: Option<usize>,
+    },
+
     // Mods
     TerminalBinary,
     Binary,
@@ -29,14 +31,21 @@ pub enum TokenKind {
     Decimal,
     TerminalHexadecimal,
     Hexadecimal,
+    // RFC 7405 %s"..." and %i"..."
+    TerminalSensitive,
+    TerminalInsensitive,
 
     // Literal TokenTypes
     String,
-    Number,
+    Prose,
     Identifier,
 
-    // Keywords *Appendix B*
-    // todo
+    // Trivia, kept so the source can be rebuilt from its tokens
     Whitespace,
+    Comment,
+    // c-nl ends a rule, c-nl followed by WSP (c-wsp) continues it
+    Newline,
+    Continuation,
+    #[allow(clippy::upper_case_acronyms)]
     EOF,
 }